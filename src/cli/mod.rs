@@ -1,27 +1,34 @@
-use crate::{Error, Priority, Status, Task};
-use chrono::NaiveDate;
-use clap::{Parser, Subcommand};
+use crate::{HumanDuration, Recurrence};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "Task Manager")]
 #[command(about = "A simple task management CLI application", version = "1.0")]
 pub struct Cli {
+    /// Task file to use instead of the default in the XDG data directory
+    #[arg(long, global = true, env = "AUTO_CAL_FILE")]
+    pub file: Option<PathBuf>,
+
     #[command(subcommand)]
     pub command: Command,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Command {
+    /// Create the task file and config file if they don't exist yet
+    Init,
     Create {
         /// Task name
         #[arg(short, long)]
         name: String,
 
-        /// Task duration in minutes
+        /// Task duration, e.g. 90, 1h30m, 2.5h, 1d or PT1H30M
         #[arg(short, long)]
-        time: u32,
+        time: HumanDuration,
 
-        /// Task due date in YYYY-MM-DD format
+        /// Due date: YYYY-MM-DD, today, tomorrow, friday, next monday, +3d, eow or
+        /// eom, optionally followed by a time like 17:00
         #[arg(short, long)]
         due_date: String,
 
@@ -29,8 +36,49 @@ pub enum Command {
         #[arg(short, long)]
         priority: Option<String>,
 
+        /// Shortest work session, e.g. 30m or 1h
+        #[arg(short, long)]
+        chunk_size: Option<HumanDuration>,
+
+        /// Longest work session, e.g. 2h
         #[arg(short, long)]
-        chunk_size: Option<u32>,
+        max_chunk_size: Option<HumanDuration>,
+
+        /// Id of a task that must be finished first; repeat for several
+        #[arg(long)]
+        depends_on: Vec<String>,
+
+        /// Id of the task to create this one as a subtask of
+        #[arg(long)]
+        parent: Option<String>,
+
+        /// Repeat once completed: daily, weekly, monthly, yearly, every 2 weeks or 3d
+        #[arg(long)]
+        repeat: Option<Recurrence>,
+
+        /// Stop repeating after this many occurrences, this one included
+        #[arg(long, requires = "repeat")]
+        repeat_count: Option<u32>,
+
+        /// Stop repeating after this date
+        #[arg(long, requires = "repeat")]
+        repeat_until: Option<String>,
+
+        /// Tag such as work or #personal; repeat for several
+        #[arg(long)]
+        tag: Vec<String>,
+
+        /// Project the task belongs to
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Longer notes on the task; may span several lines
+        #[arg(long)]
+        description: Option<String>,
+
+        /// URL or file path with more context; repeat for several
+        #[arg(long)]
+        link: Vec<String>,
     },
     List {
         #[arg(short, long, action)]
@@ -38,6 +86,22 @@ pub enum Command {
 
         #[arg(short, long)]
         count: Option<u32>,
+
+        /// Only tasks with this tag; repeat to require several
+        #[arg(long)]
+        tag: Vec<String>,
+
+        /// Hide tasks with this tag, e.g. --exclude-tag personal
+        #[arg(long)]
+        exclude_tag: Vec<String>,
+
+        /// Only tasks in this project; repeat to allow several
+        #[arg(long)]
+        project: Vec<String>,
+
+        /// Hide tasks in this project
+        #[arg(long)]
+        exclude_project: Vec<String>,
     },
     Start {
         #[arg(short, long)]
@@ -51,6 +115,94 @@ pub enum Command {
         #[arg(short, long)]
         id: String,
     },
+    /// Add a timestamped note to a task
+    Annotate {
+        #[arg(short, long)]
+        id: String,
+
+        /// The note; opens $EDITOR to write it when omitted
+        text: Option<String>,
+    },
+    /// Show everything about a task, including its description, links and notes
+    Show {
+        #[arg(short, long)]
+        id: String,
+    },
+    /// Plan open tasks into time blocks within working hours
+    Schedule {
+        /// Show the last generated schedule instead of planning a new one
+        #[arg(short, long, action)]
+        show: bool,
+    },
+    /// Check whether open tasks can be finished before their due dates
+    Check,
+    /// Import events from an iCalendar (.ics) file as time the scheduler must avoid
+    ImportBusy {
+        /// Path to the .ics file
+        #[arg(short, long)]
+        path: String,
+    },
+    /// Export the generated schedule for use in other calendar apps
+    Export {
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Ics)]
+        format: ExportFormat,
+
+        /// File to write to, or standard output when omitted
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Export the tasks themselves as todos instead of the scheduled blocks
+        #[arg(short, long, action)]
+        todos: bool,
+
+        /// Include completed tasks when exporting todos
+        #[arg(short, long, action)]
+        all: bool,
+    },
+    /// Import todos from an iCalendar (.ics) file as tasks
+    ImportTodos {
+        /// Path to the .ics file
+        #[arg(short, long)]
+        path: String,
+    },
+    /// Roll the task file back to a previous version. Backups are only kept
+    /// for the yaml and json storage backends, not for sqlite
+    Restore {
+        /// Which backup to restore, 1 being the most recent
+        #[arg(short, long, default_value_t = 1)]
+        backup: usize,
+
+        /// List the available backups instead of restoring one
+        #[arg(short, long, action)]
+        list: bool,
+    },
+    /// Revert the most recent create, edit, start, stop, complete, note or import
+    Undo {
+        /// How many operations to revert
+        #[arg(short, long, default_value_t = 1)]
+        steps: usize,
+    },
+    /// Reapply operations reverted by undo
+    Redo {
+        /// How many operations to reapply
+        #[arg(short, long, default_value_t = 1)]
+        steps: usize,
+    },
+    /// Show everything that happened to a task
+    History {
+        #[arg(short, long)]
+        id: String,
+    },
+    /// Show every recorded change, oldest first
+    Log {
+        /// Only changes on or after this date, e.g. 2024-12-01 or monday
+        #[arg(short, long)]
+        since: Option<String>,
+
+        /// Only changes on or before this date
+        #[arg(short, long)]
+        until: Option<String>,
+    },
     Edit {
         #[arg(short, long)]
         id: String,
@@ -59,11 +211,12 @@ pub enum Command {
         #[arg(short, long)]
         name: Option<String>,
 
-        /// Task duration in minutes
+        /// Task duration, e.g. 90, 1h30m, 2.5h, 1d or PT1H30M
         #[arg(short, long)]
-        time: Option<u32>,
+        time: Option<HumanDuration>,
 
-        /// Task due date in YYYY-MM-DD format
+        /// Due date: YYYY-MM-DD, today, tomorrow, friday, next monday, +3d, eow or
+        /// eom, optionally followed by a time like 17:00
         #[arg(short, long)]
         due_date: Option<String>,
 
@@ -74,14 +227,90 @@ pub enum Command {
         #[arg(short, long)]
         priority: Option<String>,
 
+        /// Shortest work session, e.g. 30m or 1h
+        #[arg(short, long)]
+        chunk_size: Option<HumanDuration>,
+
+        /// Longest work session, e.g. 2h
         #[arg(short, long)]
-        chunk_size: Option<u32>,
+        max_chunk_size: Option<HumanDuration>,
 
+        /// Time already spent, e.g. 45m
         #[arg(short, long)]
-        elapsed_time: Option<u32>,
+        elapsed_time: Option<HumanDuration>,
+
+        /// Id of a task that must be finished first; repeat for several
+        #[arg(long)]
+        depends_on: Vec<String>,
+
+        /// Id of a task to no longer wait for; repeat for several
+        #[arg(long)]
+        remove_dependency: Vec<String>,
+
+        /// Id of the task to move this one under as a subtask
+        #[arg(long, conflicts_with = "no_parent")]
+        parent: Option<String>,
+
+        /// Make this a top-level task again
+        #[arg(long, action)]
+        no_parent: bool,
+
+        /// Repeat once completed: daily, weekly, monthly, yearly, every 2 weeks or 3d
+        #[arg(long, conflicts_with = "no_repeat")]
+        repeat: Option<Recurrence>,
+
+        /// Stop repeating after this many occurrences, this one included
+        #[arg(long, conflicts_with = "no_repeat")]
+        repeat_count: Option<u32>,
+
+        /// Stop repeating after this date
+        #[arg(long, conflicts_with = "no_repeat")]
+        repeat_until: Option<String>,
+
+        /// Stop repeating the task
+        #[arg(long, action)]
+        no_repeat: bool,
+
+        /// Tag to add, such as work or #personal; repeat for several
+        #[arg(long)]
+        tag: Vec<String>,
+
+        /// Tag to remove; repeat for several
+        #[arg(long)]
+        remove_tag: Vec<String>,
+
+        /// Move the task to this project
+        #[arg(long, conflicts_with = "no_project")]
+        project: Option<String>,
+
+        /// Take the task out of its project
+        #[arg(long, action)]
+        no_project: bool,
+
+        /// Replace the description; an empty one removes it
+        #[arg(long, conflicts_with = "edit_description")]
+        description: Option<String>,
+
+        /// Edit the description in $EDITOR
+        #[arg(long, action)]
+        edit_description: bool,
+
+        /// URL or file path to add; repeat for several
+        #[arg(long)]
+        link: Vec<String>,
+
+        /// Link to remove; repeat for several
+        #[arg(long)]
+        remove_link: Vec<String>,
     },
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    /// iCalendar (RFC 5545)
+    Ics,
+}
+
 impl Cli {
     pub fn parse_cli() -> Self {
         Cli::parse()
@@ -1,73 +1,177 @@
+use crate::cli::ExportFormat;
 use crate::prelude::*;
 
-pub fn handle_create(
-    name: String,
-    time: u32,
-    due_date: String,
-    priority: Option<String>,
-    minimum_chunk_size: Option<u32>,
-) -> Result<()> {
-    let due_date = NaiveDate::parse_from_str(&due_date, "%Y-%m-%d").map_err(|_| {
-        Error::Generic(format!(
-            "Could not parse the due date: {}. Expected format: YYYY-MM-DD",
-            due_date
-        ))
-    })?;
+/// Only commands that deal in wall-clock times read the zone, so a bad
+/// setting doesn't stop the rest from working.
+fn time_zone(paths: &DataPaths) -> Result<Zone> {
+    read_config(&paths.config)?.zone()
+}
+
+pub fn handle_init(paths: &DataPaths) -> Result<()> {
+    let created = initialize_store(paths)?;
+
+    for path in [&paths.tasks, &paths.config] {
+        if created.contains(path) {
+            println!("Created {}", path.display());
+        } else {
+            println!("Already exists: {}", path.display());
+        }
+    }
+    Ok(())
+}
 
-    let priority = parse_priority(priority).unwrap_or(Priority::Medium);
+pub fn handle_create(paths: &DataPaths, payload: TaskCreatePayload) -> Result<()> {
+    let config = read_config(&paths.config)?;
+    let today = config.zone()?.today();
+    let due_date = parse_due_date(&payload.due_date, today)?;
+    let workday = config.workday_length;
+    let time = payload.time_remaining.to_minutes(workday)?;
+    let minimum_chunk_size = payload
+        .minimum_chunk_size
+        .map(|size| size.to_minutes(workday))
+        .transpose()?;
+    let maximum_chunk_size = payload
+        .maximum_chunk_size
+        .map(|size| size.to_minutes(workday))
+        .transpose()?;
 
-    add_task(
-        name,
+    let priority = parse_priority(payload.priority).unwrap_or(Priority::Medium);
+
+    let _lock = StoreLock::acquire(&paths.tasks)?;
+    let mut store = open_task_store(paths)?;
+    let mut task = create_task(
+        payload.name,
         time,
         due_date,
         priority,
         minimum_chunk_size,
-        "tasks.yaml",
+        maximum_chunk_size,
     )?;
+    // Nothing depends on or sits under a new task yet, so it can't close a cycle
+    let tasks = store.load()?;
+    task.depends_on = resolve_dependencies(&payload.depends_on, &tasks)?;
+    task.parent = payload
+        .parent
+        .map(|prefix| resolve_parent(&prefix, &tasks))
+        .transpose()?;
+    for tag in normalize_tags(&payload.tags)? {
+        if !task.tags.contains(&tag) {
+            task.tags.push(tag);
+        }
+    }
+    task.project = payload
+        .project
+        .map(|project| normalize_project(&project))
+        .transpose()?;
+    task.description = payload
+        .description
+        .and_then(|description| normalize_description(&description));
+    for link in &payload.links {
+        let link = normalize_link(link)?;
+        if !task.links.contains(&link) {
+            task.links.push(link);
+        }
+    }
+    task.recurrence = payload.repeat;
+    if let Some(recurrence) = task.recurrence.as_mut() {
+        let until = payload
+            .repeat_until
+            .map(|input| parse_due_date(&input, today))
+            .transpose()?;
+        recurrence.limit(payload.repeat_count, until.map(|due| due.date))?;
+    }
+    store.insert(task.clone())?;
+    Journal::new(&paths.journal).record(OperationKind::Create, None, Some(&task))?;
     println!("Created task");
     Ok(())
 }
 
-pub fn handle_list(all: bool, count: Option<u32>) -> Result<()> {
-    // Logic to list tasks (replace with your actual implementation)
+/// Lists the tasks matching `filter`. Deadlines are still checked against
+/// every task, since hidden ones take up time too.
+pub fn handle_list(
+    paths: &DataPaths,
+    all: bool,
+    count: Option<u32>,
+    filter: TaskQuery,
+) -> Result<()> {
+    let tasks = open_task_store(paths)?.load()?;
+    let config = read_config(&paths.config)?;
+    let busy = read_busy_blocks(&paths.busy)?;
+    let now = config.zone()?.now();
+    let (shown, at_risk) = list_view(&tasks, &filter, &config.availability, &busy, now);
+
     if all {
         println!("Listing all tasks...");
-        list_tasks(&read_tasks("tasks.yaml")?, count)?;
+        list_tasks(&shown, count)?;
     } else {
         print!("Listing all tasks that are not complete...");
-        list_tasks(
-            &filter_out_completed_tasks(&read_tasks("tasks.yaml")?)?,
-            count,
-        );
+        list_tasks(&filter_out_completed_tasks(&shown)?, count)?;
     }
+    list_at_risk_tasks(&at_risk)?;
 
     Ok(())
 }
 
-pub fn handle_start(id: String) -> Result<()> {
+/// The tasks `list` shows, in schedule order with subtask times rolled up,
+/// and the at-risk ones among them. Deadlines are checked on the stored
+/// figures, as `check` does; the rolled-up ones are only for display and
+/// would count subtasks twice.
+fn list_view(
+    tasks: &[Task],
+    filter: &TaskQuery,
+    availability: &Availability,
+    busy: &[BusyBlock],
+    now: chrono::NaiveDateTime,
+) -> (Vec<Task>, Vec<AtRiskTask>) {
+    let mut at_risk = check_feasibility(tasks, availability, busy, now);
+
+    let mut shown: Vec<Task> = tasks.to_vec();
+    roll_up_subtasks(&mut shown);
+    schedule_tasks(&mut shown);
+    shown.retain(|task| filter.matches(task));
+    at_risk.retain(|risk| shown.iter().any(|task| task.id == risk.task_id));
+
+    (shown, at_risk)
+}
+
+pub fn handle_start(paths: &DataPaths, id: String) -> Result<()> {
     println!("Searching for Task to Start");
-    let mut tasks = read_tasks("tasks.yaml")?;
-    match get_task(&tasks, &id) {
+    let _lock = StoreLock::acquire(&paths.tasks)?;
+    let mut store = open_task_store(paths)?;
+    match find_single_task(store.as_ref(), &id)? {
         Some(mut task) => {
-            task.start_work();
+            let before = task.clone();
+            task.start_work()?;
+            let tasks = store.load()?;
+            let blocking = open_dependencies(&task, &tasks);
+            if !blocking.is_empty() {
+                let labels: Vec<String> = blocking.iter().map(|task| task.label()).collect();
+                println!("Warning: this task is waiting on {}", labels.join(", "));
+            }
             println!("Starting Task: {}", task);
-            update_task_in_list(&mut tasks, task);
-            write_tasks_to_yaml(&mut tasks, "tasks.yaml");
+            store.update(task.clone())?;
+            Journal::new(&paths.journal).record(
+                OperationKind::Start,
+                Some(&before),
+                Some(&task),
+            )?;
         }
         None => println!("No Single Task Found"),
     }
     Ok(())
 }
 
-pub fn handle_stop(id: String) -> Result<()> {
+pub fn handle_stop(paths: &DataPaths, id: String) -> Result<()> {
     println!("Searching for Task to Stop");
-    let mut tasks = read_tasks("tasks.yaml")?;
-    match get_task(&tasks, &id) {
+    let _lock = StoreLock::acquire(&paths.tasks)?;
+    let mut store = open_task_store(paths)?;
+    match find_single_task(store.as_ref(), &id)? {
         Some(mut task) => {
-            task.stop_work();
+            let before = task.clone();
+            task.stop_work()?;
             println!("Stopping Task: {}", task);
-            update_task_in_list(&mut tasks, task);
-            write_tasks_to_yaml(&mut tasks, "tasks.yaml");
+            store.update(task.clone())?;
+            Journal::new(&paths.journal).record(OperationKind::Stop, Some(&before), Some(&task))?;
         }
         None => println!("No Single Task Found"),
     }
@@ -75,33 +179,390 @@ pub fn handle_stop(id: String) -> Result<()> {
     Ok(())
 }
 
-pub fn handle_complete(id: String) -> Result<()> {
+pub fn handle_complete(paths: &DataPaths, id: String) -> Result<()> {
     println!("Searching for Task to Complete");
-    let mut tasks = read_tasks("tasks.yaml")?;
-    match get_task(&tasks, &id) {
+    let _lock = StoreLock::acquire(&paths.tasks)?;
+    let mut store = open_task_store(paths)?;
+    match find_single_task(store.as_ref(), &id)? {
         Some(mut task) => {
-            update_status(&mut task, Status::Completed);
+            let before = task.clone();
+            update_status(&mut task, Status::Completed)?;
             println!("Completed Task: {}", task);
-            update_task_in_list(&mut tasks, task);
-            write_tasks_to_yaml(&mut tasks, "tasks.yaml");
+            store.update(task.clone())?;
+            let mut changes = vec![TaskChange::new(Some(&before), Some(&task))?];
+            changes.extend(create_next_occurrence(store.as_mut(), &task)?);
+
+            if read_config(&paths.config)?.complete_parents {
+                let mut tasks = store.load()?;
+                let mut child = task;
+                while let Some(parent) = finished_parent(&child, &tasks).cloned() {
+                    let mut completed = parent.clone();
+                    update_status(&mut completed, Status::Completed)?;
+                    println!("Completed parent task {}", completed.label());
+                    store.update(completed.clone())?;
+                    changes.push(TaskChange::new(Some(&parent), Some(&completed))?);
+                    changes.extend(create_next_occurrence(store.as_mut(), &completed)?);
+                    update_task_in_list(&mut tasks, completed.clone())?;
+                    child = completed;
+                }
+            }
+            Journal::new(&paths.journal).record_all(OperationKind::Complete, changes)?;
         }
         None => println!("No Single Task Found"),
     }
     Ok(())
 }
 
-pub fn handle_edit(id: String, task_edit: TaskEditPayload) -> Result<()> {
+/// Adds the next occurrence of a repeating task that was just completed,
+/// unless it already exists, and returns the change for the journal.
+fn create_next_occurrence(store: &mut dyn TaskStore, task: &Task) -> Result<Option<TaskChange>> {
+    let Some(next) = next_occurrence(task) else {
+        return Ok(None);
+    };
+    if store.get(&next.id)?.is_some() {
+        return Ok(None);
+    }
+
+    store.insert(next.clone())?;
+    println!("Next occurrence due {}: {}", next.due(), next.label());
+    Ok(Some(TaskChange::new(None, Some(&next))?))
+}
+
+pub fn handle_edit(paths: &DataPaths, id: String, mut task_edit: TaskEditPayload) -> Result<()> {
     println!("Searching for Task to Edit");
-    let mut tasks = read_tasks("tasks.yaml")?;
-    match get_task(&tasks, &id) {
-        Some(mut task) => {
-            let updated_task = edit_task(&task, &task_edit)?;
+    if task_edit.edit_description {
+        // The editor can stay open a while, so the store isn't locked meanwhile
+        let Some(task) = find_single_task(open_task_store(paths)?.as_ref(), &id)? else {
+            println!("No Single Task Found");
+            return Ok(());
+        };
+        task_edit.description = Some(edit_text(task.description.as_deref().unwrap_or(""))?);
+    }
+    let _lock = StoreLock::acquire(&paths.tasks)?;
+    let mut store = open_task_store(paths)?;
+    match find_single_task(store.as_ref(), &id)? {
+        Some(task) => {
+            let config = read_config(&paths.config)?;
+            let today = config.zone()?.today();
+            let mut tasks = store.load()?;
+            let updated_task = edit_task(&task, &task_edit, config.workday_length, today, &tasks)?;
+            update_task_in_list(&mut tasks, updated_task.clone())?;
+            check_dependencies(&tasks)?;
+            check_subtasks(&tasks)?;
 
             println!("Edited Task: {}", updated_task);
-            update_task_in_list(&mut tasks, updated_task);
-            write_tasks_to_yaml(&mut tasks, "tasks.yaml");
+            store.update(updated_task.clone())?;
+            let mut changes = vec![TaskChange::new(Some(&task), Some(&updated_task))?];
+            if task.status != Status::Completed && updated_task.status == Status::Completed {
+                changes.extend(create_next_occurrence(store.as_mut(), &updated_task)?);
+            }
+            Journal::new(&paths.journal).record_all(OperationKind::Edit, changes)?;
+        }
+        None => println!("No Single Task Found"),
+    }
+    Ok(())
+}
+
+/// Adds a note to a task, written in `$EDITOR` when no `text` is given.
+pub fn handle_annotate(paths: &DataPaths, id: String, text: Option<String>) -> Result<()> {
+    let text = match text {
+        Some(text) => text,
+        None => edit_text("")?,
+    };
+    let _lock = StoreLock::acquire(&paths.tasks)?;
+    let mut store = open_task_store(paths)?;
+    match find_single_task(store.as_ref(), &id)? {
+        Some(task) => {
+            let mut annotated = task.clone();
+            annotated.annotate(&text)?;
+            store.update(annotated.clone())?;
+            Journal::new(&paths.journal).record(
+                OperationKind::Annotate,
+                Some(&task),
+                Some(&annotated),
+            )?;
+            println!("Added note to {}", annotated.label());
+        }
+        None => println!("No Single Task Found"),
+    }
+    Ok(())
+}
+
+/// Prints everything about one task, with subtask times rolled up.
+pub fn handle_show(paths: &DataPaths, id: String) -> Result<()> {
+    let zone = time_zone(paths)?;
+    let mut tasks = open_task_store(paths)?.load()?;
+    roll_up_subtasks(&mut tasks);
+    match get_task(&tasks, &id) {
+        Some(task) => print!("{}", task_details(&task, &tasks, zone)),
+        None => println!("No Single Task Found"),
+    }
+    Ok(())
+}
+
+pub fn handle_schedule(paths: &DataPaths, show: bool) -> Result<()> {
+    if show {
+        list_schedule(&read_schedule(&paths.schedule)?)?;
+        return Ok(());
+    }
+
+    println!("Scheduling open tasks...");
+    let _lock = StoreLock::acquire(&paths.tasks)?;
+    let config = read_config(&paths.config)?;
+    let busy = read_busy_blocks(&paths.busy)?;
+    let tasks = open_task_store(paths)?.load()?;
+    let now = config.zone()?.now();
+    let blocks = generate_schedule(&tasks, &config.availability, &busy, now);
+
+    write_schedule_to_yaml(&blocks, &paths.schedule)?;
+    list_schedule(&blocks)?;
+    Ok(())
+}
+
+pub fn handle_check(paths: &DataPaths) -> Result<()> {
+    println!("Checking deadlines...");
+    let config = read_config(&paths.config)?;
+    let busy = read_busy_blocks(&paths.busy)?;
+    let tasks = open_task_store(paths)?.load()?;
+    let now = config.zone()?.now();
+    let at_risk = check_feasibility(&tasks, &config.availability, &busy, now);
+
+    if at_risk.is_empty() {
+        println!("All open tasks fit before their due dates");
+    } else {
+        list_at_risk_tasks(&at_risk)?;
+    }
+    Ok(())
+}
+
+pub fn handle_import_busy(paths: &DataPaths, path: String) -> Result<()> {
+    println!("Importing busy time from {}", path);
+    let contents = std::fs::read_to_string(&path)?;
+    let zone = time_zone(paths)?;
+    let now = zone.now();
+    let until = now + chrono::Duration::days(PLANNING_HORIZON_DAYS);
+    let (imported, skipped) = busy_blocks_from_calendar(&contents, &path, now, until, zone)?;
+
+    for message in &skipped {
+        println!("{}", message);
+    }
+
+    // Re-importing a calendar replaces whatever it contributed last time
+    let _lock = StoreLock::acquire(&paths.tasks)?;
+    let mut busy = read_busy_blocks(&paths.busy)?;
+    busy.retain(|block| block.source != path);
+    busy.extend(imported.iter().cloned());
+    busy.sort_by_key(|block| block.start);
+
+    write_busy_blocks_to_yaml(&busy, &paths.busy)?;
+    println!("Imported {} busy blocks", imported.len());
+    Ok(())
+}
+
+pub fn handle_export(
+    paths: &DataPaths,
+    format: ExportFormat,
+    output: Option<String>,
+    todos: bool,
+    all: bool,
+) -> Result<()> {
+    let tasks = open_task_store(paths)?.load()?;
+    let zone = time_zone(paths)?;
+
+    let (contents, summary) = if todos {
+        let tasks = if all {
+            tasks
+        } else {
+            filter_out_completed_tasks(&tasks)?
+        };
+        let contents = match format {
+            ExportFormat::Ics => tasks_to_calendar(&tasks, Utc::now(), zone),
+        };
+        (contents, format!("{} tasks", tasks.len()))
+    } else {
+        let blocks = read_schedule(&paths.schedule)?;
+        let contents = match format {
+            ExportFormat::Ics => schedule_to_calendar(&blocks, &tasks, Utc::now(), zone),
+        };
+        (contents, format!("{} scheduled blocks", blocks.len()))
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, contents)?;
+            println!("Exported {} to {}", summary, path);
+        }
+        None => print!("{}", contents),
+    }
+    Ok(())
+}
+
+pub fn handle_import_todos(paths: &DataPaths, path: String) -> Result<()> {
+    println!("Importing todos from {}", path);
+    let contents = std::fs::read_to_string(&path)?;
+    let (imported, skipped) = tasks_from_calendar(&contents, time_zone(paths)?)?;
+
+    for message in &skipped {
+        println!("{}", message);
+    }
+
+    let count = imported.len();
+    let _lock = StoreLock::acquire(&paths.tasks)?;
+    let mut store = open_task_store(paths)?;
+    let existing = store.load()?;
+    let mut tasks = existing.clone();
+    for message in merge_imported_tasks(&mut tasks, imported) {
+        println!("{}", message);
+    }
+    check_dependencies(&tasks)?;
+    check_subtasks(&tasks)?;
+
+    let mut changes = Vec::new();
+    for task in &tasks {
+        let old = existing.iter().find(|old| old.id == task.id);
+        if old.is_none() || !describe_changes(old, Some(task))?.is_empty() {
+            changes.push(TaskChange::new(old, Some(task))?);
+        }
+    }
+    if !changes.is_empty() {
+        // Written in one go so a large import rotates the backups only once
+        store.save_all(tasks)?;
+        Journal::new(&paths.journal).record_all(OperationKind::Import, changes)?;
+    }
+    println!("Imported {} tasks", count);
+    Ok(())
+}
+
+pub fn handle_restore(paths: &DataPaths, backup: usize, list: bool) -> Result<()> {
+    if list {
+        let zone = time_zone(paths)?;
+        let backups = list_backups(&paths.tasks)?;
+        if backups.is_empty() {
+            println!("No backups of {}", paths.tasks.display());
+        }
+        for backup in backups {
+            let modified: DateTime<Utc> = backup.modified.into();
+            println!(
+                "{}: {} ({})",
+                backup.index,
+                zone.local(modified).format("%Y-%m-%d %H:%M:%S"),
+                backup.path.display()
+            );
+        }
+        return Ok(());
+    }
+
+    let mut store = match read_config(&paths.config)?.storage.backend {
+        StorageBackend::Yaml => FileStore::yaml(&paths.tasks),
+        StorageBackend::Json => FileStore::json(&paths.tasks),
+        StorageBackend::Sqlite => {
+            return Err(Error::Generic(
+                "Backups are only kept for the yaml and json storage backends".to_string(),
+            ))
+        }
+    };
+
+    let _lock = StoreLock::acquire(&paths.tasks)?;
+    let tasks = store.restore(backup)?;
+    println!("Restored backup {} with {} tasks", backup, tasks.len());
+    Ok(())
+}
+
+pub fn handle_undo(paths: &DataPaths, steps: usize) -> Result<()> {
+    let _lock = StoreLock::acquire(&paths.tasks)?;
+    let mut store = open_task_store(paths)?;
+    let journal = Journal::new(&paths.journal);
+
+    for _ in 0..steps {
+        match journal.undo(store.as_mut())? {
+            Some(operation) => list_reverted_operation(&operation, true)?,
+            None => {
+                println!("Nothing to undo");
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn handle_redo(paths: &DataPaths, steps: usize) -> Result<()> {
+    let _lock = StoreLock::acquire(&paths.tasks)?;
+    let mut store = open_task_store(paths)?;
+    let journal = Journal::new(&paths.journal);
+
+    for _ in 0..steps {
+        match journal.redo(store.as_mut())? {
+            Some(operation) => list_reverted_operation(&operation, false)?,
+            None => {
+                println!("Nothing to redo");
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn handle_history(paths: &DataPaths, id: String) -> Result<()> {
+    let zone = time_zone(paths)?;
+    let entries = Journal::new(&paths.journal).entries()?;
+    // Tasks removed by undo only live on in the journal
+    let task = match find_single_task(open_task_store(paths)?.as_ref(), &id)? {
+        Some(task) => Some(task),
+        None => find_journaled_task(&entries, &id),
+    };
+
+    match task {
+        Some(task) => {
+            println!("History of {} ({})", task.name, task.id);
+            list_history(&task_history(&task, &entries, zone)?, zone)?;
         }
         None => println!("No Single Task Found"),
     }
     Ok(())
 }
+
+pub fn handle_log(paths: &DataPaths, since: Option<String>, until: Option<String>) -> Result<()> {
+    let zone = time_zone(paths)?;
+    let since = since
+        .map(|date| parse_due_date(&date, zone.today()).map(|due| due.date))
+        .transpose()?;
+    let until = until
+        .map(|date| parse_due_date(&date, zone.today()).map(|due| due.date))
+        .transpose()?;
+    let entries = Journal::new(&paths.journal).entries()?;
+
+    list_history(&journal_log(&entries, since, until, zone)?, zone)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    #[test]
+    fn list_and_check_agree_on_subtasks() {
+        let monday = NaiveDate::from_ymd_opt(2024, 12, 2).unwrap();
+        let project = Task {
+            time_remaining: 0,
+            due_date: monday,
+            ..sample_task("project")
+        };
+        let draft = Task {
+            time_remaining: 240,
+            due_date: monday,
+            parent: Some(project.id),
+            ..sample_task("draft")
+        };
+        let tasks = vec![project, draft];
+        let availability = Availability::default();
+        let now = monday.and_hms_opt(8, 0, 0).unwrap();
+
+        let (shown, listed) = list_view(&tasks, &TaskQuery::default(), &availability, &[], now);
+        let checked = check_feasibility(&tasks, &availability, &[], now);
+
+        assert!(checked.is_empty());
+        assert_eq!(listed, checked);
+        assert_eq!(shown[0].time_remaining, 240);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Config {
+    pub availability: Availability,
+    pub storage: StorageConfig,
+    /// How long `1d` is when durations are given in days
+    #[serde(with = "duration_minutes")]
+    pub workday_length: u32,
+    /// Zone for due times, the schedule and displayed timestamps: `local`,
+    /// `UTC`, an offset like `+02:00` or a name like `Europe/Berlin`
+    pub timezone: String,
+    /// Complete a task once all of its subtasks are completed
+    pub complete_parents: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            availability: Availability::default(),
+            storage: StorageConfig::default(),
+            workday_length: DEFAULT_WORKDAY_MINUTES,
+            timezone: LOCAL_TIME_ZONE.to_string(),
+            complete_parents: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn zone(&self) -> Result<Zone> {
+        self.timezone.parse()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+}
@@ -5,6 +5,36 @@ pub enum Error {
     #[error("Generic {0}")]
     Generic(String), // For beginning only
 
+    #[error("Task is already in progress")]
+    AlreadyInProgress,
+
+    #[error("Task is not currently in progress")]
+    NotInProgress,
+
+    #[error("Task is already completed")]
+    TaskCompleted,
+
+    #[error("Task has been deleted")]
+    TaskDeleted,
+
+    #[error("Cannot change status from {from} to {to}")]
+    InvalidTransition {
+        from: crate::task::Status,
+        to: crate::task::Status,
+    },
+
+    #[error("Dependency cycle: {0}")]
+    DependencyCycle(String),
+
+    #[error("Subtask cycle: {0}")]
+    SubtaskCycle(String),
+
+    #[error("Timed out after {seconds}s waiting for {path}; another auto-cal command may still be running")]
+    LockTimeout { path: String, seconds: u64 },
+
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+
     #[error(transparent)]
     IO(#[from] std::io::Error),
 }
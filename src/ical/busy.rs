@@ -0,0 +1,328 @@
+use crate::ical::rrule::RecurrenceRule;
+use crate::ical::*;
+use chrono::{Duration, NaiveDateTime};
+
+/// Expands every VEVENT in `input` into busy blocks overlapping `from..until`.
+///
+/// Events that can't be understood are skipped rather than failing the whole
+/// import; a message for each one is returned alongside the blocks, as is a
+/// warning for each event whose TZID isn't a known time zone.
+pub fn busy_blocks_from_calendar(
+    input: &str,
+    source: &str,
+    from: NaiveDateTime,
+    until: NaiveDateTime,
+    zone: Zone,
+) -> Result<(Vec<BusyBlock>, Vec<String>)> {
+    let calendar = parse_calendar(input)?;
+    let events = calendar.find_all("VEVENT");
+
+    // Instances moved or edited via RECURRENCE-ID replace the generated occurrence
+    let mut overridden: Vec<(String, CalendarTime)> = Vec::new();
+    for event in &events {
+        if let (Some(uid), Some(recurrence_id)) =
+            (event.property("UID"), event.property("RECURRENCE-ID"))
+        {
+            if let Ok(time) = property_time(recurrence_id) {
+                overridden.push((uid.value.clone(), time));
+            }
+        }
+    }
+
+    let mut blocks = Vec::new();
+    let mut skipped = Vec::new();
+    for event in events {
+        let summary = event
+            .property("SUMMARY")
+            .map(Property::text)
+            .unwrap_or_else(|| "Busy".to_string());
+        if let Some(tzid) = event.properties.iter().find_map(unknown_time_zone) {
+            skipped.push(format!(
+                "Event {} uses unknown time zone {}; its times were taken as local time",
+                summary, tzid
+            ));
+        }
+
+        match event_occurrences(event, &overridden, until, zone) {
+            Ok(occurrences) => blocks.extend(
+                occurrences
+                    .into_iter()
+                    .filter(|(start, end)| *end > from && *start < until)
+                    .map(|(start, end)| BusyBlock {
+                        source: source.to_string(),
+                        summary: summary.clone(),
+                        start,
+                        end,
+                    }),
+            ),
+            Err(e) => skipped.push(format!("Skipped event {}: {}", summary, e)),
+        }
+    }
+
+    blocks.sort_by_key(|block| block.start);
+    Ok((blocks, skipped))
+}
+
+fn event_occurrences(
+    event: &Component,
+    overridden: &[(String, CalendarTime)],
+    until: NaiveDateTime,
+    zone: Zone,
+) -> Result<Vec<(NaiveDateTime, NaiveDateTime)>> {
+    let is = |name: &str, value: &str| {
+        event
+            .property(name)
+            .is_some_and(|property| property.value.eq_ignore_ascii_case(value))
+    };
+    if is("STATUS", "CANCELLED") || is("TRANSP", "TRANSPARENT") {
+        return Ok(Vec::new());
+    }
+
+    let start = property_time(
+        event
+            .property("DTSTART")
+            .ok_or_else(|| Error::Generic("missing DTSTART".to_string()))?,
+    )?;
+    // Rules repeat on the wall clock of the event's own zone, so occurrences
+    // are expanded there and only converted to `zone` at the end
+    let own_zone = start.zone().unwrap_or(zone);
+    let first = start.start_of(own_zone);
+    let end = match (event.property("DTEND"), event.property("DURATION")) {
+        (Some(end), _) => property_time(end)?.start_of(own_zone),
+        (None, Some(duration)) => first
+            .checked_add_signed(parse_calendar_duration(&duration.value)?)
+            .ok_or_else(|| Error::Generic(format!("DURATION is too long: {}", duration.value)))?,
+        (None, None) => match start {
+            CalendarTime::Date(_) => first + Duration::days(1),
+            _ => first,
+        },
+    };
+    let length = end - first;
+    if length <= Duration::zero() {
+        return Ok(Vec::new());
+    }
+
+    // Leaves room for the zones' offsets; the caller drops anything past `until`
+    let horizon = until + Duration::days(2);
+    let mut starts = match event.property("RRULE") {
+        Some(rule) if event.property("RECURRENCE-ID").is_none() => rule
+            .value
+            .parse::<RecurrenceRule>()?
+            .occurrences(first, horizon, own_zone)?,
+        _ => vec![first],
+    };
+    for rdate in event.properties_named("RDATE") {
+        for time in property_times(rdate)? {
+            starts.push(time.start_of(own_zone));
+        }
+    }
+
+    let mut excluded = Vec::new();
+    for exdate in event.properties_named("EXDATE") {
+        excluded.extend(property_times(exdate)?);
+    }
+    let uid = event.property("UID").map(|uid| uid.value.as_str());
+    let is_master = event.property("RECURRENCE-ID").is_none();
+
+    starts.retain(|occurrence| {
+        let is_excluded = excluded.iter().any(|exdate| match exdate {
+            CalendarTime::Date(date) => occurrence.date() == *date,
+            exdate => *occurrence == exdate.start_of(own_zone),
+        });
+        let is_overridden = is_master
+            && overridden.iter().any(|(id, time)| {
+                Some(id.as_str()) == uid && time.start_of(own_zone) == *occurrence
+            });
+        !is_excluded && !is_overridden
+    });
+
+    let to_zone = |at: NaiveDateTime| match start.zone() {
+        Some(own_zone) => own_zone.utc(at).map_or(at, |at| zone.local(at)),
+        None => at,
+    };
+    starts
+        .into_iter()
+        .map(|start| {
+            let end = start
+                .checked_add_signed(length)
+                .ok_or_else(|| Error::Generic("event ends too late".to_string()))?;
+            Ok((to_zone(start), to_zone(end)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 12, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR
+VERSION:2.0
+BEGIN:VEVENT
+UID:standup
+SUMMARY:Standup
+DTSTART:20241202T093000
+DURATION:PT15M
+RRULE:FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR
+EXDATE:20241204T093000
+END:VEVENT
+BEGIN:VEVENT
+UID:standup
+RECURRENCE-ID:20241205T093000
+SUMMARY:Standup (moved)
+DTSTART:20241205T110000
+DTEND:20241205T111500
+END:VEVENT
+BEGIN:VEVENT
+UID:offsite
+SUMMARY:Offsite
+DTSTART;VALUE=DATE:20241206
+END:VEVENT
+BEGIN:VEVENT
+UID:optional
+SUMMARY:Optional talk
+TRANSP:TRANSPARENT
+DTSTART:20241203T140000
+DTEND:20241203T150000
+END:VEVENT
+END:VCALENDAR
+";
+
+    #[test]
+    fn expands_recurrences_exdates_and_overrides() {
+        let (blocks, skipped) =
+            busy_blocks_from_calendar(CALENDAR, "work.ics", at(2, 0, 0), at(7, 0, 0), Zone::Local)
+                .unwrap();
+
+        assert!(skipped.is_empty());
+        let starts: Vec<NaiveDateTime> = blocks.iter().map(|block| block.start).collect();
+        assert_eq!(
+            starts,
+            vec![
+                at(2, 9, 30),
+                at(3, 9, 30),
+                at(5, 11, 0),
+                at(6, 0, 0),
+                at(6, 9, 30)
+            ]
+        );
+        assert_eq!(blocks[0].end, at(2, 9, 45));
+        assert_eq!(blocks[3].summary, "Offsite");
+        assert_eq!(blocks[3].end, at(7, 0, 0));
+        assert!(blocks.iter().all(|block| block.source == "work.ics"));
+    }
+
+    #[test]
+    fn skips_events_it_cannot_understand() {
+        let input = "BEGIN:VCALENDAR
+BEGIN:VEVENT
+SUMMARY:Odd
+DTSTART:20241202T090000
+DTEND:20241202T100000
+RRULE:FREQ=MONTHLY;BYSETPOS=1
+END:VEVENT
+END:VCALENDAR
+";
+        let (blocks, skipped) =
+            busy_blocks_from_calendar(input, "odd.ics", at(1, 0, 0), at(31, 0, 0), Zone::Local)
+                .unwrap();
+
+        assert!(blocks.is_empty());
+        assert_eq!(skipped.len(), 1);
+    }
+
+    #[test]
+    fn skips_events_whose_times_overflow() {
+        let input = "BEGIN:VCALENDAR
+BEGIN:VEVENT
+SUMMARY:Forever
+DTSTART:20241202T090000
+DTEND:20241202T100000
+RRULE:FREQ=WEEKLY;INTERVAL=4000000000
+END:VEVENT
+BEGIN:VEVENT
+SUMMARY:Endless
+DTSTART:20241203T090000
+DURATION:P9999999999999W
+END:VEVENT
+BEGIN:VEVENT
+SUMMARY:Lunch
+DTSTART:20241204T120000
+DURATION:PT1H
+END:VEVENT
+END:VCALENDAR
+";
+        let (blocks, skipped) =
+            busy_blocks_from_calendar(input, "odd.ics", at(1, 0, 0), at(31, 0, 0), Zone::Local)
+                .unwrap();
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].summary, "Lunch");
+        assert_eq!(skipped.len(), 2);
+    }
+
+    #[test]
+    fn utc_times_land_in_the_configured_zone() {
+        let input = "BEGIN:VCALENDAR
+BEGIN:VEVENT
+SUMMARY:Call
+DTSTART:20241202T090000Z
+DTEND:20241202T093000Z
+END:VEVENT
+END:VCALENDAR
+";
+        let zone: Zone = "+02:00".parse().unwrap();
+
+        let (blocks, _) =
+            busy_blocks_from_calendar(input, "call.ics", at(1, 0, 0), at(31, 0, 0), zone).unwrap();
+
+        assert_eq!(
+            (blocks[0].start, blocks[0].end),
+            (at(2, 11, 0), at(2, 11, 30))
+        );
+    }
+
+    #[test]
+    fn zoned_events_repeat_on_their_own_wall_clock() {
+        let input = "BEGIN:VCALENDAR
+BEGIN:VEVENT
+SUMMARY:Sync
+DTSTART;TZID=America/New_York:20241028T090000
+DTEND;TZID=America/New_York:20241028T093000
+RRULE:FREQ=WEEKLY;COUNT=2
+END:VEVENT
+BEGIN:VEVENT
+SUMMARY:Launch
+DTSTART;TZID=Mars/Olympus:20241029T090000
+DURATION:PT1H
+END:VEVENT
+END:VCALENDAR
+";
+        let utc: Zone = "UTC".parse().unwrap();
+        let day = |month, day, hour| {
+            NaiveDate::from_ymd_opt(2024, month, day)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap()
+        };
+
+        let (blocks, warnings) =
+            busy_blocks_from_calendar(input, "sync.ics", day(10, 1, 0), day(12, 1, 0), utc)
+                .unwrap();
+
+        // New York leaves daylight saving time between the two
+        let starts: Vec<NaiveDateTime> = blocks.iter().map(|block| block.start).collect();
+        assert_eq!(
+            starts,
+            vec![day(10, 28, 13), day(10, 29, 9), day(11, 4, 14)]
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("Mars/Olympus"));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::prelude::*;
+use chrono::NaiveDateTime;
+
+const PRODUCT_ID: &str = "-//auto-cal//auto-cal//EN";
+
+/// Builds an iCalendar document line by line, folding long lines at 75
+/// octets and ending each with CRLF as RFC 5545 requires.
+#[derive(Default)]
+pub struct CalendarWriter {
+    output: String,
+}
+
+impl CalendarWriter {
+    pub fn new() -> Self {
+        let mut writer = CalendarWriter::default();
+        writer.line("BEGIN", "VCALENDAR");
+        writer.line("VERSION", "2.0");
+        writer.line("PRODID", PRODUCT_ID);
+        writer
+    }
+
+    pub fn line(&mut self, name: &str, value: &str) {
+        let line = format!("{}:{}", name, value);
+        let mut width = 0;
+        for c in line.chars() {
+            if width + c.len_utf8() > 75 {
+                self.output.push_str("\r\n ");
+                width = 1;
+            }
+            self.output.push(c);
+            width += c.len_utf8();
+        }
+        self.output.push_str("\r\n");
+    }
+
+    pub fn text(&mut self, name: &str, value: &str) {
+        self.line(name, &escape_text(value));
+    }
+
+    pub fn finish(mut self) -> String {
+        self.line("END", "VCALENDAR");
+        self.output
+    }
+}
+
+pub fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Local wall-clock time, written as an iCalendar "floating" time.
+pub fn format_local_time(date_time: NaiveDateTime) -> String {
+    date_time.format("%Y%m%dT%H%M%S").to_string()
+}
+
+pub fn format_utc_time(date_time: DateTime<Utc>) -> String {
+    date_time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Wall-clock time in `zone`, written in UTC so calendar apps in any zone put
+/// it at the right instant. A time skipped by a daylight saving change has no
+/// instant and stays floating.
+pub fn format_zoned_time(date_time: NaiveDateTime, zone: Zone) -> String {
+    zone.utc(date_time)
+        .map_or_else(|| format_local_time(date_time), format_utc_time)
+}
+
+/// Writes each scheduled block as a VEVENT. UIDs are derived from the task id
+/// and the block's position within that task, so exporting again after
+/// rescheduling updates the same events instead of adding duplicates. Block
+/// times are wall-clock times in `zone`.
+pub fn schedule_to_calendar(
+    blocks: &[TimeBlock],
+    tasks: &[Task],
+    stamp: DateTime<Utc>,
+    zone: Zone,
+) -> String {
+    let mut writer = CalendarWriter::new();
+    let mut sessions_seen: Vec<(Uuid, usize)> = Vec::new();
+
+    for block in blocks {
+        let session = match sessions_seen
+            .iter_mut()
+            .find(|(id, _)| *id == block.task_id)
+        {
+            Some((_, count)) => {
+                *count += 1;
+                *count
+            }
+            None => {
+                sessions_seen.push((block.task_id, 1));
+                1
+            }
+        };
+
+        writer.line("BEGIN", "VEVENT");
+        writer.line("UID", &format!("{}-{}@auto-cal", block.task_id, session));
+        writer.line("DTSTAMP", &format_utc_time(stamp));
+        writer.line("DTSTART", &format_zoned_time(block.start, zone));
+        writer.line("DTEND", &format_zoned_time(block.end, zone));
+        writer.text("SUMMARY", &block.task_name);
+        if let Some(task) = tasks.iter().find(|task| task.id == block.task_id) {
+            writer.text(
+                "DESCRIPTION",
+                &format!("Priority: {}\nStatus: {}", task.priority_level, task.status),
+            );
+        }
+        writer.line("END", "VEVENT");
+    }
+
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ical::{parse_calendar, property_time, CalendarTime};
+    use crate::test_support::sample_task;
+
+    #[test]
+    fn exported_schedule_parses_back() {
+        let task = Task {
+            time_remaining: 120,
+            priority_level: Priority::High,
+            ..sample_task("Write report, draft 2")
+        };
+        let day = NaiveDate::from_ymd_opt(2024, 12, 2).unwrap();
+        let blocks: Vec<TimeBlock> = [(9, 10), (13, 14)]
+            .iter()
+            .map(|(start, end)| TimeBlock {
+                task_id: task.id,
+                task_name: task.name.clone(),
+                start: day.and_hms_opt(*start, 0, 0).unwrap(),
+                end: day.and_hms_opt(*end, 0, 0).unwrap(),
+            })
+            .collect();
+
+        let zone: Zone = "Europe/Berlin".parse().unwrap();
+        let output = schedule_to_calendar(&blocks, std::slice::from_ref(&task), Utc::now(), zone);
+        let calendar = parse_calendar(&output).unwrap();
+        let parsed = calendar.find_all("VEVENT");
+
+        assert_eq!(parsed.len(), 2);
+        assert!(output.lines().all(|line| line.len() <= 76));
+        assert_eq!(
+            parsed[0].property("UID").unwrap().value,
+            format!("{}-1@auto-cal", task.id)
+        );
+        assert_eq!(
+            parsed[1].property("UID").unwrap().value,
+            format!("{}-2@auto-cal", task.id)
+        );
+        assert_eq!(parsed[0].property("SUMMARY").unwrap().text(), task.name);
+        assert_eq!(
+            parsed[0].property("DESCRIPTION").unwrap().text(),
+            "Priority: High\nStatus: UnStarted"
+        );
+        assert_eq!(
+            property_time(parsed[1].property("DTSTART").unwrap()).unwrap(),
+            CalendarTime::Utc(day.and_hms_opt(12, 0, 0).unwrap().and_utc())
+        );
+    }
+}
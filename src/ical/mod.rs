@@ -0,0 +1,388 @@
+//! Minimal iCalendar (RFC 5545) support: enough to read events from other
+//! calendar apps and to write our own back out.
+
+use crate::prelude::*;
+use chrono::{Duration, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+
+pub mod busy;
+pub mod export;
+pub mod rrule;
+pub mod todo;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The value with TEXT escapes (`\n`, `\,`, `\;`, `\\`) resolved.
+    pub fn text(&self) -> String {
+        unescape_text(&self.value)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+impl Component {
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn properties_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> {
+        self.properties
+            .iter()
+            .filter(move |property| property.name.eq_ignore_ascii_case(name))
+    }
+
+    /// All nested components called `name`, at any depth.
+    pub fn find_all(&self, name: &str) -> Vec<&Component> {
+        let mut found = Vec::new();
+        for component in &self.components {
+            if component.name.eq_ignore_ascii_case(name) {
+                found.push(component);
+            }
+            found.extend(component.find_all(name));
+        }
+        found
+    }
+}
+
+/// A DATE or DATE-TIME value. `DateTime` is a "floating" wall-clock time,
+/// `Utc` a fixed instant and `Zoned` a wall-clock time in a named zone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalendarTime {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+    Utc(DateTime<Utc>),
+    Zoned(NaiveDateTime, Tz),
+}
+
+impl CalendarTime {
+    /// The wall-clock time in `zone` this value starts at.
+    pub fn start_of(&self, zone: Zone) -> NaiveDateTime {
+        match self {
+            CalendarTime::Date(date) => date.and_hms_opt(0, 0, 0).unwrap(),
+            CalendarTime::DateTime(date_time) => *date_time,
+            CalendarTime::Utc(at) => zone.local(*at),
+            // Falls back to the wall-clock time when it was skipped by a DST change
+            CalendarTime::Zoned(date_time, tz) => Zone::Named(*tz)
+                .utc(*date_time)
+                .map_or(*date_time, |at| zone.local(at)),
+        }
+    }
+
+    /// The zone the value is tied to, or `None` for dates and floating times.
+    pub fn zone(&self) -> Option<Zone> {
+        match self {
+            CalendarTime::Utc(_) => Some(Zone::Fixed(chrono::FixedOffset::east_opt(0).unwrap())),
+            CalendarTime::Zoned(_, tz) => Some(Zone::Named(*tz)),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a whole iCalendar document into its component tree. The returned
+/// root has no name and holds the top-level `VCALENDAR` component(s).
+pub fn parse_calendar(input: &str) -> Result<Component> {
+    let mut stack = vec![Component::default()];
+
+    for line in unfold_lines(input) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let property = parse_content_line(&line)?;
+
+        if property.name.eq_ignore_ascii_case("BEGIN") {
+            stack.push(Component {
+                name: property.value.to_uppercase(),
+                ..Component::default()
+            });
+        } else if property.name.eq_ignore_ascii_case("END") {
+            let component = stack
+                .pop()
+                .filter(|component| component.name.eq_ignore_ascii_case(&property.value))
+                .ok_or_else(|| {
+                    Error::Generic(format!("Unexpected END:{} in calendar", property.value))
+                })?;
+            stack
+                .last_mut()
+                .ok_or_else(|| {
+                    Error::Generic(format!("Unexpected END:{} in calendar", property.value))
+                })?
+                .components
+                .push(component);
+        } else {
+            stack
+                .last_mut()
+                .expect("root component is never popped")
+                .properties
+                .push(property);
+        }
+    }
+
+    if stack.len() != 1 {
+        return Err(Error::Generic(
+            "Calendar ended before all components were closed".to_string(),
+        ));
+    }
+
+    Ok(stack.pop().unwrap())
+}
+
+/// Joins folded lines: a line starting with a space or tab continues the previous one.
+fn unfold_lines(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for raw in input.lines() {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(previous)) => previous.push_str(continuation),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+
+    lines
+}
+
+fn parse_content_line(line: &str) -> Result<Property> {
+    // Find the first ':' and every ';' that isn't inside a quoted parameter value
+    let mut in_quotes = false;
+    let mut separators = Vec::new();
+    let mut colon = None;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => separators.push(i),
+            ':' if !in_quotes => {
+                colon = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let colon =
+        colon.ok_or_else(|| Error::Generic(format!("Malformed calendar line: {}", line)))?;
+    let head = &line[..colon];
+    let value = line[colon + 1..].to_string();
+
+    let mut parts = Vec::new();
+    let mut last = 0;
+    for &i in &separators {
+        parts.push(&head[last..i]);
+        last = i + 1;
+    }
+    parts.push(&head[last..]);
+
+    let name = parts[0].trim().to_uppercase();
+    if name.is_empty() {
+        return Err(Error::Generic(format!("Malformed calendar line: {}", line)));
+    }
+
+    let params = parts[1..]
+        .iter()
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.trim().to_uppercase(),
+                value.trim_matches('"').to_string(),
+            )
+        })
+        .collect();
+
+    Ok(Property {
+        name,
+        params,
+        value,
+    })
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+/// Parses a DATE or DATE-TIME value as written, without looking at a TZID.
+pub fn parse_calendar_time(value: &str, value_type: Option<&str>) -> Result<CalendarTime> {
+    let value = value.trim();
+    let invalid = || Error::Generic(format!("Could not parse calendar date: {}", value));
+
+    if value_type.is_some_and(|t| t.eq_ignore_ascii_case("DATE")) || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map(CalendarTime::Date)
+            .map_err(|_| invalid());
+    }
+
+    match value.strip_suffix(['Z', 'z']) {
+        Some(utc) => {
+            let naive =
+                NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+            Ok(CalendarTime::Utc(Utc.from_utc_datetime(&naive)))
+        }
+        None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .map(CalendarTime::DateTime)
+            .map_err(|_| invalid()),
+    }
+}
+
+/// Parses a property's DATE or DATE-TIME value. Times with a TZID that names
+/// a zone in the tz database are tied to that zone; see `unknown_time_zone`
+/// for the others.
+pub fn property_time(property: &Property) -> Result<CalendarTime> {
+    let time = parse_calendar_time(&property.value, property.param("VALUE"))?;
+    Ok(in_property_zone(property, time))
+}
+
+/// Every value of a list property such as RDATE or EXDATE.
+pub fn property_times(property: &Property) -> Result<Vec<CalendarTime>> {
+    property
+        .value
+        .split(',')
+        .map(|value| {
+            let time = parse_calendar_time(value, property.param("VALUE"))?;
+            Ok(in_property_zone(property, time))
+        })
+        .collect()
+}
+
+fn in_property_zone(property: &Property, time: CalendarTime) -> CalendarTime {
+    match (
+        time,
+        property.param("TZID").and_then(|tzid| tzid.parse().ok()),
+    ) {
+        (CalendarTime::DateTime(date_time), Some(tz)) => CalendarTime::Zoned(date_time, tz),
+        _ => time,
+    }
+}
+
+/// The TZID of `property` if auto-cal can't resolve it, in which case its
+/// times are taken as wall-clock time in the configured zone.
+pub fn unknown_time_zone(property: &Property) -> Option<&str> {
+    property
+        .param("TZID")
+        .filter(|tzid| tzid.parse::<Tz>().is_err())
+}
+
+/// Parses an RFC 5545 DURATION such as `PT1H30M`, `P1D` or `-P2W`.
+pub fn parse_calendar_duration(value: &str) -> Result<Duration> {
+    let invalid = || Error::Generic(format!("Could not parse calendar duration: {}", value));
+    let value = value.trim();
+
+    let (sign, rest) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let rest = rest.strip_prefix(['P', 'p']).ok_or_else(invalid)?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in rest.chars() {
+        match c.to_ascii_uppercase() {
+            'T' => in_time = true,
+            '0'..='9' => number.push(c),
+            unit => {
+                let amount: i64 = number.parse().map_err(|_| invalid())?;
+                number.clear();
+                let part = match (unit, in_time) {
+                    ('W', false) => Duration::try_weeks(amount),
+                    ('D', false) => Duration::try_days(amount),
+                    ('H', true) => Duration::try_hours(amount),
+                    ('M', true) => Duration::try_minutes(amount),
+                    ('S', true) => Duration::try_seconds(amount),
+                    _ => return Err(invalid()),
+                };
+                total = part
+                    .and_then(|part| total.checked_add(&part))
+                    .ok_or_else(|| {
+                        Error::Generic(format!("Calendar duration is too long: {}", value))
+                    })?;
+            }
+        }
+    }
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok(total * sign)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_components_with_folded_lines() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nSUMMARY:Team\r\n  sync\\, weekly\r\nDTSTART;TZID=\"Europe/London\":20241202T090000\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+
+        let root = parse_calendar(input).unwrap();
+        let events = root.find_all("VEVENT");
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].property("SUMMARY").unwrap().text(),
+            "Team sync, weekly"
+        );
+        let start = events[0].property("DTSTART").unwrap();
+        assert_eq!(start.param("tzid"), Some("Europe/London"));
+        assert_eq!(
+            property_time(start).unwrap(),
+            CalendarTime::Zoned(
+                NaiveDate::from_ymd_opt(2024, 12, 2)
+                    .unwrap()
+                    .and_hms_opt(9, 0, 0)
+                    .unwrap(),
+                chrono_tz::Europe::London
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_unbalanced_components() {
+        assert!(parse_calendar("BEGIN:VCALENDAR\nBEGIN:VEVENT\nEND:VCALENDAR\n").is_err());
+        assert!(parse_calendar("BEGIN:VCALENDAR\n").is_err());
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(
+            parse_calendar_duration("PT1H30M").unwrap(),
+            Duration::minutes(90)
+        );
+        assert_eq!(parse_calendar_duration("P1D").unwrap(), Duration::days(1));
+        assert_eq!(
+            parse_calendar_duration("-P2W").unwrap(),
+            Duration::weeks(-2)
+        );
+        assert!(parse_calendar_duration("1H").is_err());
+    }
+}
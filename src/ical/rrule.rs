@@ -0,0 +1,404 @@
+use crate::ical::{parse_calendar_time, CalendarTime};
+use crate::prelude::*;
+use chrono::{Datelike, Days, Duration, Months, NaiveDateTime, Weekday};
+use std::str::FromStr;
+
+/// Stops runaway rules (e.g. an unbounded BYDAY that never matches) from looping forever.
+const MAX_PERIODS: u32 = 100_000;
+
+/// An RRULE's FREQ. Named apart from the `Frequency` of task recurrence,
+/// which the prelude brings into scope.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuleFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The subset of RFC 5545 RRULE that calendar apps commonly emit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceRule {
+    pub frequency: RuleFrequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<CalendarTime>,
+    /// Weekdays with an optional ordinal, e.g. `-1FR` for the last Friday of the month
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    pub by_month_day: Vec<i32>,
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |part: &str| Error::Generic(format!("Unsupported RRULE part: {}", part));
+
+        let mut frequency = None;
+        let mut rule = RecurrenceRule {
+            frequency: RuleFrequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+        };
+
+        for part in s.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(|| invalid(part))?;
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_uppercase().as_str() {
+                        "DAILY" => RuleFrequency::Daily,
+                        "WEEKLY" => RuleFrequency::Weekly,
+                        "MONTHLY" => RuleFrequency::Monthly,
+                        "YEARLY" => RuleFrequency::Yearly,
+                        _ => return Err(invalid(part)),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|i| *i > 0)
+                        .ok_or_else(|| invalid(part))?
+                }
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| invalid(part))?),
+                "UNTIL" => rule.until = Some(parse_calendar_time(value, None)?),
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(parse_by_day)
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| invalid(part))?
+                }
+                "BYMONTHDAY" => {
+                    rule.by_month_day = value
+                        .split(',')
+                        .map(|day| day.parse().ok().filter(|d: &i32| *d != 0 && d.abs() <= 31))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| invalid(part))?
+                }
+                // Week start only matters for rules we don't support
+                "WKST" => {}
+                _ => return Err(invalid(part)),
+            }
+        }
+
+        rule.frequency = frequency.ok_or_else(|| invalid("missing FREQ"))?;
+        if rule.frequency == RuleFrequency::Yearly
+            && !(rule.by_day.is_empty() && rule.by_month_day.is_empty())
+        {
+            return Err(invalid("BY* parts on a YEARLY rule"));
+        }
+        // RFC 5545 doesn't allow BYMONTHDAY on weekly rules
+        if rule.frequency == RuleFrequency::Weekly && !rule.by_month_day.is_empty() {
+            return Err(invalid("BYMONTHDAY on a WEEKLY rule"));
+        }
+        Ok(rule)
+    }
+}
+
+fn parse_by_day(value: &str) -> Option<(Option<i32>, Weekday)> {
+    let value = value.trim();
+    if value.len() < 2 {
+        return None;
+    }
+    let (ordinal, day) = value.split_at(value.len() - 2);
+    let weekday = match day.to_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    let ordinal = match ordinal {
+        "" => None,
+        n => Some(n.parse().ok().filter(|n: &i32| *n != 0)?),
+    };
+    Some((ordinal, weekday))
+}
+
+impl RecurrenceRule {
+    /// Every occurrence start from `start` (the event's DTSTART) up to and
+    /// including `until`, honouring COUNT and UNTIL. Times are wall-clock
+    /// times in `zone`. Fails if the INTERVAL steps past the dates chrono can
+    /// represent before reaching `until`.
+    pub fn occurrences(
+        &self,
+        start: NaiveDateTime,
+        until: NaiveDateTime,
+        zone: Zone,
+    ) -> Result<Vec<NaiveDateTime>> {
+        let last = self
+            .until
+            .map_or(until, |rule_until| rule_until.start_of(zone).min(until));
+        let mut occurrences = Vec::new();
+        let mut emitted = 0;
+
+        for period in 0..MAX_PERIODS {
+            let period_start = self.period_start(start, period).ok_or_else(|| {
+                Error::Generic(format!("INTERVAL={} is too large", self.interval))
+            })?;
+            if period_start.date() > last.date() {
+                break;
+            }
+
+            let mut candidates = self.candidates_in_period(start, period_start);
+            candidates.sort();
+
+            for candidate in candidates.into_iter().filter(|c| *c >= start) {
+                if candidate > last || self.count.is_some_and(|count| emitted >= count) {
+                    return Ok(occurrences);
+                }
+                emitted += 1;
+                occurrences.push(candidate);
+            }
+        }
+
+        Ok(occurrences)
+    }
+
+    fn period_start(&self, start: NaiveDateTime, period: u32) -> Option<NaiveDateTime> {
+        let step = period.checked_mul(self.interval)?;
+        match self.frequency {
+            RuleFrequency::Daily => start.checked_add_signed(Duration::try_days(step as i64)?),
+            RuleFrequency::Weekly => {
+                let monday = start
+                    .checked_sub_days(Days::new(start.weekday().num_days_from_monday() as u64))?;
+                monday.checked_add_signed(Duration::try_weeks(step as i64)?)
+            }
+            RuleFrequency::Monthly => first_of_month(start).checked_add_months(Months::new(step)),
+            RuleFrequency::Yearly => {
+                first_of_month(start).checked_add_months(Months::new(step.checked_mul(12)?))
+            }
+        }
+    }
+
+    fn candidates_in_period(
+        &self,
+        start: NaiveDateTime,
+        period_start: NaiveDateTime,
+    ) -> Vec<NaiveDateTime> {
+        let time = start.time();
+        match self.frequency {
+            RuleFrequency::Daily => {
+                let date = period_start.date();
+                let day_matches = self.by_day.is_empty()
+                    || self.by_day.iter().any(|(_, day)| *day == date.weekday());
+                let month_day_matches = self.by_month_day.is_empty()
+                    || self
+                        .by_month_day
+                        .iter()
+                        .any(|day| month_day(date.year(), date.month(), *day) == Some(date));
+                if day_matches && month_day_matches {
+                    vec![period_start]
+                } else {
+                    Vec::new()
+                }
+            }
+            RuleFrequency::Weekly => {
+                let days: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![start.weekday()]
+                } else {
+                    self.by_day.iter().map(|(_, day)| *day).collect()
+                };
+                days.iter()
+                    .filter_map(|day| {
+                        let offset = Days::new(day.num_days_from_monday() as u64);
+                        Some(period_start.date().checked_add_days(offset)?.and_time(time))
+                    })
+                    .collect()
+            }
+            RuleFrequency::Monthly => {
+                let (year, month) = (period_start.year(), period_start.month());
+                let by_day = |date: &NaiveDate| {
+                    self.by_day.iter().any(|(ordinal, day)| {
+                        weekdays_in_month(year, month, *ordinal, *day).contains(date)
+                    })
+                };
+                let dates: Vec<NaiveDate> = if !self.by_month_day.is_empty() {
+                    // With BYDAY as well, only days matching both count
+                    self.by_month_day
+                        .iter()
+                        .filter_map(|day| month_day(year, month, *day))
+                        .filter(|date| self.by_day.is_empty() || by_day(date))
+                        .collect()
+                } else if !self.by_day.is_empty() {
+                    self.by_day
+                        .iter()
+                        .flat_map(|(ordinal, day)| weekdays_in_month(year, month, *ordinal, *day))
+                        .collect()
+                } else {
+                    // Months without the start's day of month are skipped, per RFC 5545
+                    NaiveDate::from_ymd_opt(year, month, start.day())
+                        .into_iter()
+                        .collect()
+                };
+                dates.into_iter().map(|date| date.and_time(time)).collect()
+            }
+            RuleFrequency::Yearly => {
+                NaiveDate::from_ymd_opt(period_start.year(), start.month(), start.day())
+                    .map(|date| date.and_time(time))
+                    .into_iter()
+                    .collect()
+            }
+        }
+    }
+}
+
+fn first_of_month(date_time: NaiveDateTime) -> NaiveDateTime {
+    date_time
+        .date()
+        .with_day(1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+    let next = first + Months::new(1);
+    (next - first).num_days() as u32
+}
+
+/// Day `day` of the month, counting from the end when negative.
+fn month_day(year: i32, month: u32, day: i32) -> Option<NaiveDate> {
+    let length = days_in_month(year, month) as i32;
+    let day = if day < 0 { length + day + 1 } else { day };
+    if day < 1 {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(year, month, day as u32)
+}
+
+fn weekdays_in_month(
+    year: i32,
+    month: u32,
+    ordinal: Option<i32>,
+    weekday: Weekday,
+) -> Vec<NaiveDate> {
+    let matching: Vec<NaiveDate> = (1..=days_in_month(year, month))
+        .filter_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+        .filter(|date| date.weekday() == weekday)
+        .collect();
+
+    match ordinal {
+        None => matching,
+        Some(n) if n > 0 => matching.get(n as usize - 1).copied().into_iter().collect(),
+        Some(n) => matching
+            .len()
+            .checked_sub(n.unsigned_abs() as usize)
+            .and_then(|i| matching.get(i).copied())
+            .into_iter()
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn weekly_rule_with_count() {
+        let rule: RecurrenceRule = "FREQ=WEEKLY;BYDAY=MO,WE;COUNT=3".parse().unwrap();
+
+        let occurrences = rule
+            .occurrences(at(2024, 12, 2, 10), at(2025, 12, 31, 0), Zone::Local)
+            .unwrap();
+
+        assert_eq!(
+            occurrences,
+            vec![
+                at(2024, 12, 2, 10),
+                at(2024, 12, 4, 10),
+                at(2024, 12, 9, 10)
+            ]
+        );
+    }
+
+    #[test]
+    fn daily_rule_stops_at_until() {
+        let rule: RecurrenceRule = "FREQ=DAILY;INTERVAL=2;UNTIL=20241206T235959"
+            .parse()
+            .unwrap();
+
+        let occurrences = rule
+            .occurrences(at(2024, 12, 2, 9), at(2025, 12, 31, 0), Zone::Local)
+            .unwrap();
+
+        assert_eq!(
+            occurrences,
+            vec![at(2024, 12, 2, 9), at(2024, 12, 4, 9), at(2024, 12, 6, 9)]
+        );
+    }
+
+    #[test]
+    fn monthly_rules_by_weekday_and_month_day() {
+        let last_friday: RecurrenceRule = "FREQ=MONTHLY;BYDAY=-1FR;COUNT=2".parse().unwrap();
+        assert_eq!(
+            last_friday
+                .occurrences(at(2024, 11, 29, 15), at(2025, 12, 31, 0), Zone::Local)
+                .unwrap(),
+            vec![at(2024, 11, 29, 15), at(2024, 12, 27, 15)]
+        );
+
+        let the_31st: RecurrenceRule = "FREQ=MONTHLY;COUNT=2".parse().unwrap();
+        assert_eq!(
+            the_31st
+                .occurrences(at(2024, 10, 31, 9), at(2025, 12, 31, 0), Zone::Local)
+                .unwrap(),
+            vec![at(2024, 10, 31, 9), at(2024, 12, 31, 9)]
+        );
+    }
+
+    #[test]
+    fn month_days_and_weekdays_intersect() {
+        let friday_13th: RecurrenceRule = "FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13;COUNT=2"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            friday_13th
+                .occurrences(at(2024, 9, 13, 9), at(2026, 12, 31, 0), Zone::Local)
+                .unwrap(),
+            vec![at(2024, 9, 13, 9), at(2024, 12, 13, 9)]
+        );
+        assert!("FREQ=WEEKLY;BYMONTHDAY=1"
+            .parse::<RecurrenceRule>()
+            .is_err());
+    }
+
+    #[test]
+    fn unbounded_rules_stop_at_the_horizon() {
+        let rule: RecurrenceRule = "FREQ=YEARLY".parse().unwrap();
+
+        let occurrences = rule
+            .occurrences(at(2024, 2, 29, 9), at(2033, 1, 1, 0), Zone::Local)
+            .unwrap();
+
+        assert_eq!(
+            occurrences,
+            vec![at(2024, 2, 29, 9), at(2028, 2, 29, 9), at(2032, 2, 29, 9)]
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_parts() {
+        assert!("FREQ=MONTHLY;BYSETPOS=-1"
+            .parse::<RecurrenceRule>()
+            .is_err());
+        assert!("FREQ=HOURLY".parse::<RecurrenceRule>().is_err());
+        assert!("INTERVAL=2".parse::<RecurrenceRule>().is_err());
+    }
+}
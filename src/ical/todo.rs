@@ -0,0 +1,408 @@
+use crate::ical::*;
+
+/// Lets a round trip through another app restore what iCalendar can't express.
+const X_STATUS: &str = "X-AUTO-CAL-STATUS";
+const X_TIME_REMAINING: &str = "X-AUTO-CAL-TIME-REMAINING";
+const X_ELAPSED_TIME: &str = "X-AUTO-CAL-ELAPSED-TIME";
+const X_MINIMUM_CHUNK_SIZE: &str = "X-AUTO-CAL-MINIMUM-CHUNK-SIZE";
+const X_MAXIMUM_CHUNK_SIZE: &str = "X-AUTO-CAL-MAXIMUM-CHUNK-SIZE";
+
+/// Namespace for ids derived from UIDs that aren't UUIDs, so importing the
+/// same todo again finds the task made from it the first time.
+const UID_NAMESPACE: Uuid = Uuid::from_u128(0x5f0c_8a3e_2b7d_4c1e_9a64_d3f2_8e17_b05c);
+
+/// RFC 5545 priorities run 1 (highest) to 9 (lowest), with 0 meaning undefined.
+pub fn priority_to_ical(priority: &Priority) -> u8 {
+    match priority {
+        Priority::Urgent => 1,
+        Priority::High => 3,
+        Priority::Medium => 5,
+        Priority::Low => 7,
+    }
+}
+
+pub fn priority_from_ical(value: u8) -> Priority {
+    match value {
+        1..=2 => Priority::Urgent,
+        3..=4 => Priority::High,
+        6..=9 => Priority::Low,
+        _ => Priority::Medium,
+    }
+}
+
+pub fn status_to_ical(status: &Status) -> &'static str {
+    match status {
+        Status::UnStarted | Status::OnHold => "NEEDS-ACTION",
+        Status::InProgress => "IN-PROCESS",
+        Status::Completed => "COMPLETED",
+        Status::Deleted => "CANCELLED",
+    }
+}
+
+pub fn status_from_ical(value: &str) -> Status {
+    match value.to_uppercase().as_str() {
+        "IN-PROCESS" => Status::InProgress,
+        "COMPLETED" => Status::Completed,
+        "CANCELLED" => Status::Deleted,
+        _ => Status::UnStarted,
+    }
+}
+
+pub fn percent_complete(task: &Task) -> u32 {
+    let total = task.elapsed_time + task.time_remaining;
+    if total == 0 {
+        return if task.status == Status::Completed {
+            100
+        } else {
+            0
+        };
+    }
+    task.elapsed_time * 100 / total
+}
+
+/// Writes each task as a VTODO, using the task id as the UID. Due times are
+/// wall-clock times in `zone`.
+pub fn tasks_to_calendar(tasks: &[Task], stamp: DateTime<Utc>, zone: Zone) -> String {
+    let mut writer = CalendarWriter::new();
+
+    for task in tasks {
+        writer.line("BEGIN", "VTODO");
+        writer.line("UID", &task.id.to_string());
+        writer.line("DTSTAMP", &format_utc_time(stamp));
+        writer.line("CREATED", &format_utc_time(task.created_date));
+        writer.text("SUMMARY", &task.name);
+        if let Some(description) = &task.description {
+            writer.text("DESCRIPTION", description);
+        }
+        if !task.tags.is_empty() {
+            // Tags never contain commas, so they need no escaping
+            writer.line("CATEGORIES", &task.tags.join(","));
+        }
+        match task.due_time {
+            Some(time) => writer.line(
+                "DUE",
+                &format_zoned_time(task.due_date.and_time(time), zone),
+            ),
+            None => writer.line(
+                "DUE;VALUE=DATE",
+                &task.due_date.format("%Y%m%d").to_string(),
+            ),
+        }
+        writer.line(
+            "PRIORITY",
+            &priority_to_ical(&task.priority_level).to_string(),
+        );
+        writer.line("STATUS", status_to_ical(&task.status));
+        writer.line("PERCENT-COMPLETE", &percent_complete(task).to_string());
+        writer.line(X_STATUS, &task.status.to_string());
+        writer.line(X_TIME_REMAINING, &task.time_remaining.to_string());
+        writer.line(X_ELAPSED_TIME, &task.elapsed_time.to_string());
+        if let Some(minutes) = task.minimum_chunk_size {
+            writer.line(X_MINIMUM_CHUNK_SIZE, &minutes.to_string());
+        }
+        if let Some(minutes) = task.maximum_chunk_size {
+            writer.line(X_MAXIMUM_CHUNK_SIZE, &minutes.to_string());
+        }
+        writer.line("END", "VTODO");
+    }
+
+    writer.finish()
+}
+
+/// Reads every VTODO in `input` as a task, with due times that are fixed
+/// instants converted to `zone`. Todos without a due date can't be scheduled,
+/// so they are skipped with a message instead of failing the import.
+pub fn tasks_from_calendar(input: &str, zone: Zone) -> Result<(Vec<Task>, Vec<String>)> {
+    let calendar = parse_calendar(input)?;
+    let mut tasks = Vec::new();
+    let mut skipped = Vec::new();
+
+    for todo in calendar.find_all("VTODO") {
+        let name = todo
+            .property("SUMMARY")
+            .map(Property::text)
+            .unwrap_or_else(|| "Untitled".to_string());
+
+        match task_from_todo(todo, &name, zone) {
+            Ok(task) => tasks.push(task),
+            Err(e) => skipped.push(format!("Skipped todo {}: {}", name, e)),
+        }
+    }
+
+    Ok((tasks, skipped))
+}
+
+fn task_from_todo(todo: &Component, name: &str, zone: Zone) -> Result<Task> {
+    let minutes = |property: &str| -> Result<Option<u32>> {
+        todo.property(property)
+            .map(|p| {
+                p.value
+                    .trim()
+                    .parse()
+                    .map_err(|_| Error::Generic(format!("Invalid {}: {}", property, p.value)))
+            })
+            .transpose()
+    };
+
+    let due = match todo.property("DUE") {
+        Some(due) => match property_time(due)? {
+            CalendarTime::Date(date) => DueDate::from(date),
+            time => {
+                let date_time = time.start_of(zone);
+                DueDate {
+                    date: date_time.date(),
+                    time: Some(date_time.time()),
+                }
+            }
+        },
+        None => return Err(Error::Generic("missing DUE".to_string())),
+    };
+
+    let status = match todo.property(X_STATUS) {
+        Some(status) => status.value.parse()?,
+        None => todo
+            .property("STATUS")
+            .map_or(Status::UnStarted, |status| status_from_ical(&status.value)),
+    };
+
+    let time_remaining = match (minutes(X_TIME_REMAINING)?, todo.property("DURATION")) {
+        (Some(minutes), _) => minutes,
+        (None, Some(duration)) => parse_calendar_duration(&duration.value)?
+            .num_minutes()
+            .max(0) as u32,
+        (None, None) => 0,
+    };
+
+    let created_date = match todo.property("CREATED") {
+        Some(created) => match property_time(created)? {
+            CalendarTime::Utc(at) => at,
+            // CREATED must be UTC; treat anything else as UTC too
+            other => other.start_of(zone).and_utc(),
+        },
+        None => Utc::now(),
+    };
+
+    let mut task = Task {
+        id: todo.property("UID").map_or_else(Uuid::new_v4, |uid| {
+            let uid = uid.value.trim();
+            Uuid::parse_str(uid).unwrap_or_else(|_| Uuid::new_v5(&UID_NAMESPACE, uid.as_bytes()))
+        }),
+        name: name.to_string(),
+        time_remaining,
+        elapsed_time: minutes(X_ELAPSED_TIME)?.unwrap_or(0),
+        due_date: due.date,
+        due_time: due.time,
+        depends_on: Vec::new(),
+        parent: None,
+        recurrence: None,
+        // Categories from other apps may not be valid tags; those are dropped
+        tags: todo
+            .properties_named("CATEGORIES")
+            .flat_map(|categories| categories.value.split(','))
+            .filter_map(|category| normalize_tag(category).ok())
+            .collect(),
+        project: None,
+        description: todo
+            .property("DESCRIPTION")
+            .and_then(|description| normalize_description(&description.text())),
+        links: Vec::new(),
+        annotations: Vec::new(),
+        status: Status::UnStarted,
+        created_date,
+        priority_level: todo
+            .property("PRIORITY")
+            .and_then(|priority| priority.value.trim().parse().ok())
+            .map_or(Priority::Medium, priority_from_ical),
+        minimum_chunk_size: minutes(X_MINIMUM_CHUNK_SIZE)?,
+        maximum_chunk_size: minutes(X_MAXIMUM_CHUNK_SIZE)?,
+        work_intervals: Vec::new(),
+        status_history: Vec::new(),
+    };
+    // Goes through the usual rules, so an imported task in progress has an
+    // open work interval that `stop` can close
+    task.transition_to(status)?;
+    Ok(task)
+}
+
+/// Adds imported tasks to the list, updating any task with the same id in
+/// place. Work intervals, status history, relations to other tasks, the
+/// project, links and notes aren't exported, so existing ones are kept, and
+/// status changes go through the usual transition rules; a
+/// message is returned for each change that was rejected.
+pub fn merge_imported_tasks(tasks: &mut Vec<Task>, imported: Vec<Task>) -> Vec<String> {
+    let mut rejected = Vec::new();
+
+    for mut task in imported {
+        match tasks.iter_mut().find(|existing| existing.id == task.id) {
+            Some(existing) => {
+                let status = std::mem::replace(&mut task.status, existing.status.clone());
+                task.work_intervals = std::mem::take(&mut existing.work_intervals);
+                task.status_history = std::mem::take(&mut existing.status_history);
+                task.depends_on = std::mem::take(&mut existing.depends_on);
+                task.parent = existing.parent;
+                task.recurrence = existing.recurrence.take();
+                task.project = existing.project.take();
+                task.links = std::mem::take(&mut existing.links);
+                task.annotations = std::mem::take(&mut existing.annotations);
+                if let Err(e) = task.transition_to(status) {
+                    rejected.push(format!("Kept status of {}: {}", task.name, e));
+                }
+                *existing = task;
+            }
+            None => tasks.push(task),
+        }
+    }
+
+    rejected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    /// A todo with every exported field set and text that needs escaping.
+    fn invoice() -> Task {
+        Task {
+            status: Status::OnHold,
+            created_date: NaiveDate::from_ymd_opt(2024, 11, 1)
+                .unwrap()
+                .and_hms_opt(8, 15, 0)
+                .unwrap()
+                .and_utc(),
+            priority_level: Priority::High,
+            minimum_chunk_size: Some(15),
+            elapsed_time: 90,
+            ..sample_task("Invoice; client A")
+        }
+    }
+
+    #[test]
+    fn maps_fields_onto_rfc_5545() {
+        let task = invoice();
+
+        let output = tasks_to_calendar(std::slice::from_ref(&task), Utc::now(), Zone::Local);
+        let calendar = parse_calendar(&output).unwrap();
+        let todo = calendar.find_all("VTODO")[0];
+
+        assert_eq!(todo.property("UID").unwrap().value, task.id.to_string());
+        assert_eq!(todo.property("DUE").unwrap().value, "20241231");
+        assert_eq!(todo.property("PRIORITY").unwrap().value, "3");
+        assert_eq!(todo.property("STATUS").unwrap().value, "NEEDS-ACTION");
+        assert_eq!(todo.property("PERCENT-COMPLETE").unwrap().value, "75");
+    }
+
+    #[test]
+    fn writes_due_times_in_utc() {
+        let task = Task {
+            due_time: NaiveTime::from_hms_opt(17, 0, 0),
+            ..invoice()
+        };
+        let zone: Zone = "America/New_York".parse().unwrap();
+
+        let output = tasks_to_calendar(std::slice::from_ref(&task), Utc::now(), zone);
+        let (imported, _) = tasks_from_calendar(&output, zone).unwrap();
+
+        assert!(output.contains("DUE:20241231T220000Z"));
+        assert_eq!(imported[0].due_time, task.due_time);
+    }
+
+    #[test]
+    fn round_trips_tasks() {
+        let task = invoice();
+
+        let output = tasks_to_calendar(std::slice::from_ref(&task), Utc::now(), Zone::Local);
+        let (imported, skipped) = tasks_from_calendar(&output, Zone::Local).unwrap();
+
+        assert!(skipped.is_empty());
+        let round_tripped = &imported[0];
+        assert_eq!(round_tripped.id, task.id);
+        assert_eq!(round_tripped.name, task.name);
+        assert_eq!(round_tripped.due_date, task.due_date);
+        assert_eq!(round_tripped.status, Status::OnHold);
+        assert_eq!(round_tripped.priority_level, Priority::High);
+        assert_eq!(round_tripped.time_remaining, 30);
+        assert_eq!(round_tripped.elapsed_time, 90);
+        assert_eq!(round_tripped.minimum_chunk_size, Some(15));
+        assert_eq!(round_tripped.created_date, task.created_date);
+        assert_eq!(round_tripped.tags, task.tags);
+        assert_eq!(round_tripped.description, task.description);
+    }
+
+    #[test]
+    fn imports_todos_from_other_apps() {
+        let input = "BEGIN:VCALENDAR
+BEGIN:VTODO
+UID:not-a-uuid@example.com
+SUMMARY:Call plumber
+DUE:20241205T170000
+PRIORITY:9
+STATUS:IN-PROCESS
+DTSTART:20241202T090000
+DURATION:PT45M
+END:VTODO
+BEGIN:VTODO
+SUMMARY:Someday
+END:VTODO
+END:VCALENDAR
+";
+        let (imported, skipped) = tasks_from_calendar(input, Zone::Local).unwrap();
+
+        assert_eq!(imported.len(), 1);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(imported[0].name, "Call plumber");
+        assert_eq!(imported[0].priority_level, Priority::Low);
+        assert_eq!(imported[0].status, Status::InProgress);
+        assert!(imported[0].work_intervals[0].1.is_none());
+        assert_eq!(imported[0].time_remaining, 45);
+        assert_eq!(
+            imported[0].due_date,
+            NaiveDate::from_ymd_opt(2024, 12, 5).unwrap()
+        );
+        assert_eq!(imported[0].due_time, NaiveTime::from_hms_opt(17, 0, 0));
+    }
+
+    #[test]
+    fn reimporting_a_todo_keeps_its_id() {
+        let input = "BEGIN:VCALENDAR
+BEGIN:VTODO
+UID:20241202T0900-17@example.com
+SUMMARY:Call plumber
+DUE;VALUE=DATE:20241205
+END:VTODO
+END:VCALENDAR
+";
+
+        let (first, _) = tasks_from_calendar(input, Zone::Local).unwrap();
+        let (second, _) = tasks_from_calendar(input, Zone::Local).unwrap();
+        let mut tasks = first.clone();
+        merge_imported_tasks(&mut tasks, second.clone());
+
+        assert_eq!(first[0].id, second[0].id);
+        assert_eq!(tasks.len(), 1);
+    }
+
+    #[test]
+    fn merging_updates_existing_tasks_by_id() {
+        let mut existing = invoice();
+        existing.work_intervals = vec![(Utc::now(), Some(Utc::now()))];
+        existing.project = Some("Accounts".to_string());
+        existing.annotate("Sent reminder").unwrap();
+        let mut updated = existing.clone();
+        updated.name = "Renamed".to_string();
+        updated.work_intervals = Vec::new();
+        updated.status = Status::Completed;
+        let mut tasks = vec![existing];
+
+        let rejected = merge_imported_tasks(&mut tasks, vec![updated, invoice()]);
+
+        assert!(rejected.is_empty());
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].name, "Renamed");
+        assert_eq!(tasks[0].status, Status::Completed);
+        assert_eq!(tasks[0].work_intervals.len(), 1);
+        assert_eq!(tasks[0].status_history.len(), 1);
+        assert_eq!(tasks[0].project.as_deref(), Some("Accounts"));
+        assert_eq!(tasks[0].annotations.len(), 1);
+    }
+}
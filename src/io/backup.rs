@@ -0,0 +1,89 @@
+use crate::io::*;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// How many previous versions of the task file are kept.
+pub const BACKUP_COUNT: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+    /// 1 is the most recent
+    pub index: usize,
+    pub path: PathBuf,
+    pub modified: SystemTime,
+}
+
+/// `tasks.yaml` is backed up as `tasks.yaml.bak.1`, `tasks.yaml.bak.2`, ...
+pub fn backup_path(file_path: &Path, index: usize) -> PathBuf {
+    let mut name = file_path.as_os_str().to_owned();
+    name.push(format!(".bak.{}", index));
+    PathBuf::from(name)
+}
+
+/// Copies the current file into backup slot 1, shifting older backups down and
+/// dropping any beyond `keep`. Does nothing if the file doesn't exist yet.
+pub fn rotate_backups(file_path: &Path, keep: usize) -> Result<()> {
+    if keep == 0 || !file_path.exists() {
+        return Ok(());
+    }
+
+    let oldest = backup_path(file_path, keep);
+    if oldest.exists() {
+        std::fs::remove_file(&oldest)?;
+    }
+    for index in (1..keep).rev() {
+        let from = backup_path(file_path, index);
+        if from.exists() {
+            std::fs::rename(&from, backup_path(file_path, index + 1))?;
+        }
+    }
+
+    std::fs::copy(file_path, backup_path(file_path, 1))?;
+    Ok(())
+}
+
+pub fn list_backups(file_path: &Path) -> Result<Vec<Backup>> {
+    let mut backups = Vec::new();
+
+    for index in 1..=BACKUP_COUNT {
+        let path = backup_path(file_path, index);
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            backups.push(Backup {
+                index,
+                modified: metadata.modified()?,
+                path,
+            });
+        }
+    }
+
+    Ok(backups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+    use std::fs;
+
+    #[test]
+    fn keeps_a_rotating_set_of_backups() {
+        let temp_dir = PathBuf::from("test_backup_dir");
+        fs::create_dir_all(&temp_dir).expect("Failed to create test directory");
+        let temp_file = temp_dir.join("tasks.yaml");
+
+        let mut tasks = Vec::new();
+        for i in 0..BACKUP_COUNT + 2 {
+            tasks.push(sample_task(&format!("task {}", i)));
+            rotate_backups(&temp_file, BACKUP_COUNT).expect("Failed to rotate backups");
+            write_tasks_to_yaml(&tasks, &temp_file).expect("Failed to write tasks");
+        }
+        let backups = list_backups(&temp_file).expect("Failed to list backups");
+        let latest_backup = read_tasks(backup_path(&temp_file, 1)).expect("Failed to read backup");
+        let overflow_exists = backup_path(&temp_file, BACKUP_COUNT + 1).exists();
+
+        fs::remove_dir_all(&temp_dir).expect("Failed to delete test directory");
+        assert_eq!(backups.len(), BACKUP_COUNT);
+        assert_eq!(latest_backup.len(), BACKUP_COUNT + 1);
+        assert!(!overflow_exists);
+    }
+}
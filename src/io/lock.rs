@@ -0,0 +1,82 @@
+use crate::io::*;
+use std::fs::TryLockError;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How long a command waits for another one to finish with the task file.
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// An advisory lock on the task store, held until dropped.
+///
+/// The lock lives on a separate `<file>.lock` file because writes replace the
+/// task file by renaming over it, which would orphan a lock held on the old one.
+#[derive(Debug)]
+pub struct StoreLock {
+    file: File,
+}
+
+impl StoreLock {
+    pub fn acquire(file_path: &Path) -> Result<Self> {
+        StoreLock::acquire_with_timeout(file_path, LOCK_TIMEOUT)
+    }
+
+    pub fn acquire_with_timeout(file_path: &Path, timeout: Duration) -> Result<Self> {
+        let lock_path = lock_path(file_path);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)?;
+
+        let started = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(StoreLock { file }),
+                Err(TryLockError::WouldBlock) if started.elapsed() < timeout => {
+                    std::thread::sleep(RETRY_INTERVAL)
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(Error::LockTimeout {
+                        path: lock_path.display().to_string(),
+                        seconds: timeout.as_secs(),
+                    })
+                }
+                Err(TryLockError::Error(e)) => return Err(Error::IO(e)),
+            }
+        }
+    }
+}
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+pub fn lock_path(file_path: &Path) -> PathBuf {
+    let mut name = file_path.as_os_str().to_owned();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_lock_times_out_until_the_first_is_dropped() {
+        let temp_file = Path::new("test_lock_tasks.yaml");
+
+        let first = StoreLock::acquire(temp_file).expect("Failed to take the lock");
+        let second = StoreLock::acquire_with_timeout(temp_file, Duration::from_millis(100));
+        assert!(matches!(second, Err(Error::LockTimeout { .. })));
+
+        drop(first);
+        let third = StoreLock::acquire_with_timeout(temp_file, Duration::from_millis(100));
+        assert!(third.is_ok());
+
+        drop(third);
+        std::fs::remove_file(lock_path(temp_file)).expect("Failed to delete lock file");
+    }
+}
@@ -1,7 +1,12 @@
 use crate::prelude::*;
 
+pub mod backup;
+pub mod lock;
+pub mod paths;
 pub mod read;
+pub mod schema;
 pub mod write;
 
 pub use std::fs::{File, OpenOptions};
 pub use std::io::{Read, Write};
+pub use std::path::Path;
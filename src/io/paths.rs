@@ -0,0 +1,163 @@
+use crate::prelude::*;
+use std::path::{Path, PathBuf};
+
+pub const APP_DIR: &str = "auto-cal";
+pub const DEFAULT_TASK_STEM: &str = "tasks";
+pub const CONFIG_FILE: &str = "config.yaml";
+
+/// Every file the CLI reads or writes. The schedule and busy time live next
+/// to the task file and share its name, so separate task lists don't share them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataPaths {
+    pub tasks: PathBuf,
+    pub schedule: PathBuf,
+    pub busy: PathBuf,
+    pub journal: PathBuf,
+    pub config: PathBuf,
+}
+
+impl DataPaths {
+    /// Uses `file` when given (from `--file` or `AUTO_CAL_FILE`), otherwise
+    /// the XDG data directory, which is created on first run. The default task
+    /// file's extension follows the configured storage backend.
+    pub fn resolve(file: Option<PathBuf>) -> Result<Self> {
+        Self::resolve_with(file, |config| Ok(read_config(config)?.storage.backend))
+    }
+
+    /// Like `resolve`, but a config file that can't be read falls back to the
+    /// default backend, so `init` still works when the config needs repair.
+    /// `init` reports the broken config itself.
+    pub fn resolve_for_init(file: Option<PathBuf>) -> Result<Self> {
+        Self::resolve_with(file, |config| {
+            Ok(read_config(config)
+                .map(|config| config.storage.backend)
+                .unwrap_or_default())
+        })
+    }
+
+    fn resolve_with(
+        file: Option<PathBuf>,
+        backend: impl FnOnce(&Path) -> Result<StorageBackend>,
+    ) -> Result<Self> {
+        let config = xdg_dir("XDG_CONFIG_HOME", ".config")?.join(CONFIG_FILE);
+        let tasks = match file {
+            Some(file) => file,
+            None => {
+                let backend = backend(&config)?;
+                let data_dir = xdg_dir("XDG_DATA_HOME", ".local/share")?;
+                std::fs::create_dir_all(&data_dir)?;
+                default_task_file(&data_dir, backend)?
+            }
+        };
+
+        Ok(DataPaths::for_task_file(tasks, config))
+    }
+
+    pub fn for_task_file(tasks: PathBuf, config: PathBuf) -> Self {
+        DataPaths {
+            schedule: sibling(&tasks, "schedule", "yaml"),
+            busy: sibling(&tasks, "busy", "yaml"),
+            journal: sibling(&tasks, "journal", "jsonl"),
+            tasks,
+            config,
+        }
+    }
+}
+
+/// The default task file for `backend`. Each backend keeps its own file, so
+/// after `storage.backend` changes the new one would start out empty; that is
+/// refused while only another backend's file exists, rather than hiding every
+/// task.
+fn default_task_file(data_dir: &Path, backend: StorageBackend) -> Result<PathBuf> {
+    let file = |backend: StorageBackend| {
+        data_dir.join(format!("{}.{}", DEFAULT_TASK_STEM, backend.extension()))
+    };
+    let tasks = file(backend);
+    if tasks.exists() {
+        return Ok(tasks);
+    }
+
+    let others = StorageBackend::ALL
+        .into_iter()
+        .filter(|other| *other != backend);
+    match others.map(file).find(|other| other.exists()) {
+        Some(other) => Err(Error::Generic(format!(
+            "Tasks are stored in {} but storage.backend is set to {}. Change storage.backend back, or pass the file with --file",
+            other.display(),
+            format!("{:?}", backend).to_lowercase()
+        ))),
+        None => Ok(tasks),
+    }
+}
+
+/// `dir/name.yaml` becomes `dir/name.<kind>.<extension>`.
+fn sibling(tasks: &Path, kind: &str, extension: &str) -> PathBuf {
+    let stem = tasks
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "tasks".to_string());
+    tasks.with_file_name(format!("{}.{}.{}", stem, kind, extension))
+}
+
+/// `$<variable>/auto-cal`, falling back to `$HOME/<fallback>/auto-cal` when the
+/// variable is unset or not absolute, as the XDG base directory spec requires.
+fn xdg_dir(variable: &str, fallback: &str) -> Result<PathBuf> {
+    let base = match std::env::var_os(variable).map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => dir,
+        _ => std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(fallback))
+            .ok_or_else(|| {
+                Error::Generic(format!(
+                    "Could not find a data directory: neither {} nor HOME is set",
+                    variable
+                ))
+            })?,
+    };
+
+    Ok(base.join(APP_DIR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_sibling_files_from_the_task_file() {
+        let paths = DataPaths::for_task_file(
+            PathBuf::from("/data/work.yaml"),
+            PathBuf::from("/config/config.yaml"),
+        );
+
+        assert_eq!(paths.schedule, PathBuf::from("/data/work.schedule.yaml"));
+        assert_eq!(paths.busy, PathBuf::from("/data/work.busy.yaml"));
+        assert_eq!(paths.journal, PathBuf::from("/data/work.journal.jsonl"));
+        assert_eq!(paths.config, PathBuf::from("/config/config.yaml"));
+    }
+
+    #[test]
+    fn refuses_to_switch_backends_away_from_existing_tasks() {
+        let data_dir = std::env::temp_dir().join(format!("auto-cal-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let empty = default_task_file(&data_dir, StorageBackend::Sqlite);
+        std::fs::write(data_dir.join("tasks.yaml"), "version: 8\ntasks: []").unwrap();
+
+        let same = default_task_file(&data_dir, StorageBackend::Yaml);
+        let switched = default_task_file(&data_dir, StorageBackend::Sqlite);
+
+        std::fs::remove_dir_all(&data_dir).expect("Failed to delete test directory");
+        assert_eq!(empty.unwrap(), data_dir.join("tasks.db"));
+        assert_eq!(same.unwrap(), data_dir.join("tasks.yaml"));
+        assert!(switched.is_err());
+    }
+
+    #[test]
+    fn explicit_file_wins_over_the_default() {
+        let paths = DataPaths::resolve(Some(PathBuf::from("elsewhere/tasks.yaml"))).unwrap();
+
+        assert_eq!(paths.tasks, PathBuf::from("elsewhere/tasks.yaml"));
+        assert_eq!(
+            paths.schedule,
+            PathBuf::from("elsewhere/tasks.schedule.yaml")
+        );
+    }
+}
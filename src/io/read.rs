@@ -1,23 +1,94 @@
 use crate::io::*;
-use crate::prelude::*;
 
-pub fn read_tasks(file_path: &str) -> Result<Vec<Task>> {
-    // Open the file for reading
-    let mut file = File::open(file_path).map_err(|e| {
-        Error::IO(e) // Automatically converts std::io::Error to Error::IO
-    })?;
+/// Reads the whole file, or `None` if it doesn't exist yet.
+pub fn read_to_string_if_exists(file_path: impl AsRef<Path>) -> Result<Option<String>> {
+    match std::fs::read_to_string(file_path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::IO(e)),
+    }
+}
 
-    let mut contents = std::fs::read_to_string(file_path).unwrap_or_else(|_| String::new()); // Default to empty string if file doesn't exist
+pub fn read_tasks(file_path: impl AsRef<Path>) -> Result<Vec<Task>> {
+    // A missing or blank file is a fresh task list
+    let contents = read_to_string_if_exists(file_path)?.unwrap_or_default();
+    if contents.trim().is_empty() {
+        return Ok(Vec::new());
+    }
 
-    // Deserialize the YAML string into a Vec<Task>
-    let tasks: Vec<Task> = serde_yaml::from_str(&contents).map_err(|e| {
+    // Older files are upgraded to the current schema as they are read
+    let document: serde_json::Value = serde_yaml::from_str(&contents).map_err(|e| {
         Error::Generic(format!(
             "Failed to deserialize tasks from YAML. Error: {}",
             e
         ))
     })?;
 
-    Ok(tasks)
+    migrate_document(document)
+}
+
+pub fn read_tasks_json(file_path: impl AsRef<Path>) -> Result<Vec<Task>> {
+    let contents = read_to_string_if_exists(file_path)?.unwrap_or_default();
+    if contents.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let document: serde_json::Value = serde_json::from_str(&contents).map_err(|e| {
+        Error::Generic(format!(
+            "Failed to deserialize tasks from JSON. Error: {}",
+            e
+        ))
+    })?;
+
+    migrate_document(document)
+}
+
+pub fn read_schedule(file_path: impl AsRef<Path>) -> Result<Vec<TimeBlock>> {
+    // A schedule that has never been generated is simply empty
+    let Some(contents) = read_to_string_if_exists(file_path)? else {
+        return Ok(Vec::new());
+    };
+
+    let blocks: Vec<TimeBlock> = serde_yaml::from_str(&contents).map_err(|e| {
+        Error::Generic(format!(
+            "Failed to deserialize schedule from YAML. Error: {}",
+            e
+        ))
+    })?;
+
+    Ok(blocks)
+}
+
+pub fn read_busy_blocks(file_path: impl AsRef<Path>) -> Result<Vec<BusyBlock>> {
+    // Nothing imported yet means no busy time
+    let Some(contents) = read_to_string_if_exists(file_path)? else {
+        return Ok(Vec::new());
+    };
+
+    let blocks: Vec<BusyBlock> = serde_yaml::from_str(&contents).map_err(|e| {
+        Error::Generic(format!(
+            "Failed to deserialize busy time from YAML. Error: {}",
+            e
+        ))
+    })?;
+
+    Ok(blocks)
+}
+
+pub fn read_config(file_path: impl AsRef<Path>) -> Result<Config> {
+    // Without a config file every setting falls back to its default
+    let Some(contents) = read_to_string_if_exists(file_path)? else {
+        return Ok(Config::default());
+    };
+
+    let config: Config = serde_yaml::from_str(&contents).map_err(|e| {
+        Error::Generic(format!(
+            "Failed to deserialize config from YAML. Error: {}",
+            e
+        ))
+    })?;
+
+    Ok(config)
 }
 
 #[cfg(test)]
@@ -25,54 +96,82 @@ mod tests {
     use super::*;
     use std::fs;
 
-    //#[test]
-    //fn read_tasks_from_yaml_file() {
-    //    // Write the provided YAML to a temporary file
-    //    let temp_file = "test_tasks.yaml";
-    //    let yaml_content = r#"
-    //    - id: "5275239b-bc94-467a-b34c-141498417c7d"
-    //      name: "high priority"
-    //      time_remaining: 23
-    //      due_date: "2024-11-15"
-    //      status: "UnStarted"
-    //      created_date: "2024-11-22T01:21:52.020546800Z"
-    //      priority_level: "Urgent"
-    //
-    //    - id: "62688812-f3bd-418c-a5db-42457d63a3a7"
-    //      name: "high priority"
-    //      time_remaining: 23
-    //      due_date: "2024-11-15"
-    //      status: "UnStarted"
-    //      created_date: "2024-11-22T01:13:39.845179100Z"
-    //      priority_level: "High"
-    //    - id: "49b03535-5595-4f96-80de-dc2b8a325add"
-    //      name: "low priority"
-    //      time_remaining: 23
-    //      due_date: "2024-11-26"
-    //      status: "UnStarted"
-    //      created_date: "2024-11-22T01:27:48.823644200Z"
-    //      priority_level: "High"
-    //    "#;
-    //
-    //    fs::write(temp_file, yaml_content).expect("Failed to write YAML file");
-    //
-    //    // Test reading tasks
-    //    let result = read_tasks(temp_file);
-    //    assert!(result.is_ok());
-    //    let tasks = result.unwrap();
-    //
-    //    // Assert number of tasks read
-    //    assert_eq!(tasks.len(), 3);
-    //
-    //    // Assert individual task fields
-    //    assert_eq!(tasks[0].name, "high priority");
-    //    assert_eq!(tasks[0].priority_level, Priority::Urgent);
-    //    assert_eq!(
-    //        tasks[2].due_date,
-    //        NaiveDate::from_ymd_opt(2024, 11, 26).unwrap()
-    //    );
-    //
-    //    // Clean up
-    //    fs::remove_file(temp_file).expect("Failed to delete test file");
-    //}
+    #[test]
+    fn missing_task_file_is_an_empty_list() {
+        let tasks = read_tasks("test_missing_tasks.yaml").expect("Missing file should not error");
+        assert!(tasks.is_empty());
+    }
+
+    #[test]
+    fn empty_task_file_is_an_empty_list() {
+        let temp_file = "test_empty_tasks.yaml";
+        fs::write(temp_file, "\n").expect("Failed to write YAML file");
+
+        let result = read_tasks(temp_file);
+
+        fs::remove_file(temp_file).expect("Failed to delete test file");
+        assert!(result.expect("Empty file should not error").is_empty());
+    }
+
+    #[test]
+    fn malformed_task_file_is_an_error() {
+        let temp_file = "test_malformed_tasks.yaml";
+        fs::write(temp_file, "- id: [not, a, task").expect("Failed to write YAML file");
+
+        let result = read_tasks(temp_file);
+
+        fs::remove_file(temp_file).expect("Failed to delete test file");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn read_tasks_from_yaml_file() {
+        // Write the provided YAML to a temporary file
+        let temp_file = "test_tasks.yaml";
+        let yaml_content = r#"
+        - id: "5275239b-bc94-467a-b34c-141498417c7d"
+          name: "high priority"
+          time_remaining: 23
+          due_date: "2024-11-15"
+          status: "UnStarted"
+          created_date: "2024-11-22T01:21:52.020546800Z"
+          priority_level: "Urgent"
+    
+        - id: "62688812-f3bd-418c-a5db-42457d63a3a7"
+          name: "high priority"
+          time_remaining: 23
+          due_date: "2024-11-15"
+          status: "UnStarted"
+          created_date: "2024-11-22T01:13:39.845179100Z"
+          priority_level: "High"
+        - id: "49b03535-5595-4f96-80de-dc2b8a325add"
+          name: "low priority"
+          time_remaining: 23
+          due_date: "2024-11-26"
+          status: "UnStarted"
+          created_date: "2024-11-22T01:27:48.823644200Z"
+          priority_level: "High"
+        "#;
+
+        fs::write(temp_file, yaml_content).expect("Failed to write YAML file");
+
+        // Test reading tasks
+        let result = read_tasks(temp_file);
+        assert!(result.is_ok());
+        let tasks = result.unwrap();
+
+        // Assert number of tasks read
+        assert_eq!(tasks.len(), 3);
+
+        // Assert individual task fields
+        assert_eq!(tasks[0].name, "high priority");
+        assert_eq!(tasks[0].priority_level, Priority::Urgent);
+        assert_eq!(
+            tasks[2].due_date,
+            NaiveDate::from_ymd_opt(2024, 11, 26).unwrap()
+        );
+
+        // Clean up
+        fs::remove_file(temp_file).expect("Failed to delete test file");
+    }
 }
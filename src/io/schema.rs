@@ -0,0 +1,233 @@
+use crate::prelude::*;
+use serde_json::{Map, Value};
+
+/// Version of the task format this build writes. Bump it whenever `Task`
+/// changes shape and add a step to `MIGRATIONS` that upgrades the one before.
+pub const SCHEMA_VERSION: u32 = 8;
+
+/// Each step upgrades one task from version `i` to `i + 1`.
+const MIGRATIONS: [fn(&mut Map<String, Value>); SCHEMA_VERSION as usize] = [
+    // 0 -> 1: time tracking
+    |task| {
+        fill_default(task, "elapsed_time", Value::from(0));
+        fill_default(task, "minimum_chunk_size", Value::Null);
+        fill_default(task, "work_intervals", Value::Array(Vec::new()));
+    },
+    // 1 -> 2: chunk limits and status history
+    |task| {
+        fill_default(task, "maximum_chunk_size", Value::Null);
+        fill_default(task, "status_history", Value::Array(Vec::new()));
+    },
+    // 2 -> 3: due times
+    |task| fill_default(task, "due_time", Value::Null),
+    // 3 -> 4: dependencies
+    |task| fill_default(task, "depends_on", Value::Array(Vec::new())),
+    // 4 -> 5: subtasks
+    |task| fill_default(task, "parent", Value::Null),
+    // 5 -> 6: recurrence
+    |task| fill_default(task, "recurrence", Value::Null),
+    // 6 -> 7: tags and projects
+    |task| {
+        fill_default(task, "tags", Value::Array(Vec::new()));
+        fill_default(task, "project", Value::Null);
+    },
+    // 7 -> 8: descriptions, links and notes
+    |task| {
+        fill_default(task, "description", Value::Null);
+        fill_default(task, "links", Value::Array(Vec::new()));
+        fill_default(task, "annotations", Value::Array(Vec::new()));
+    },
+];
+
+/// What a task file holds on disk. Files written before the version header
+/// existed are a bare list of tasks and count as version 0.
+#[derive(Debug, Serialize)]
+pub struct TaskDocument<'a> {
+    pub version: u32,
+    pub tasks: &'a [Task],
+}
+
+impl<'a> TaskDocument<'a> {
+    pub fn current(tasks: &'a [Task]) -> Self {
+        TaskDocument {
+            version: SCHEMA_VERSION,
+            tasks,
+        }
+    }
+}
+
+fn fill_default(task: &mut Map<String, Value>, field: &str, default: Value) {
+    task.entry(field).or_insert(default);
+}
+
+/// Upgrades a single task from `version` to the current schema.
+pub fn migrate_task(mut task: Value, version: u32) -> Result<Task> {
+    check_supported(version)?;
+
+    let Value::Object(fields) = &mut task else {
+        return Err(Error::Generic(format!("Expected a task, found: {}", task)));
+    };
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(fields);
+    }
+
+    serde_json::from_value(task)
+        .map_err(|e| Error::Generic(format!("Failed to deserialize task. Error: {}", e)))
+}
+
+/// Reads a parsed task file of any known version, upgrading it on the way.
+pub fn migrate_document(document: Value) -> Result<Vec<Task>> {
+    let (version, tasks) = match document {
+        Value::Array(tasks) => (0, tasks),
+        Value::Object(mut fields) => {
+            let version = fields
+                .get("version")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| Error::Generic("Task file has no schema version".to_string()))?;
+            match fields.remove("tasks") {
+                Some(Value::Array(tasks)) => (version as u32, tasks),
+                Some(Value::Null) | None => (version as u32, Vec::new()),
+                Some(other) => {
+                    return Err(Error::Generic(format!(
+                        "Expected a list of tasks, found: {}",
+                        other
+                    )))
+                }
+            }
+        }
+        other => {
+            return Err(Error::Generic(format!(
+                "Expected a task file, found: {}",
+                other
+            )))
+        }
+    };
+
+    check_supported(version)?;
+    tasks
+        .into_iter()
+        .map(|task| migrate_task(task, version))
+        .collect()
+}
+
+/// Files from a newer auto-cal may hold data this one would silently drop.
+pub fn check_supported(version: u32) -> Result<()> {
+    if version > SCHEMA_VERSION {
+        return Err(Error::Generic(format!(
+            "Task file uses schema version {}, but this version of auto-cal only supports up to {}",
+            version, SCHEMA_VERSION
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_yaml(input: &str) -> Result<Vec<Task>> {
+        migrate_document(serde_yaml::from_str(input).unwrap())
+    }
+
+    #[test]
+    fn upgrades_files_from_before_time_tracking() {
+        let tasks = from_yaml(include_str!("../../tests/fixtures/tasks_v0.yaml")).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].priority_level, Priority::Urgent);
+        assert_eq!(tasks[0].elapsed_time, 0);
+        assert!(tasks[0].work_intervals.is_empty());
+        assert_eq!(tasks[1].minimum_chunk_size, None);
+    }
+
+    #[test]
+    fn upgrades_unversioned_lists_with_time_tracking() {
+        let tasks = from_yaml(include_str!("../../tests/fixtures/tasks_v1.yaml")).unwrap();
+
+        assert_eq!(tasks[0].elapsed_time, 30);
+        assert_eq!(tasks[0].minimum_chunk_size, Some(30));
+        assert_eq!(tasks[0].work_intervals.len(), 1);
+        assert_eq!(tasks[0].maximum_chunk_size, None);
+        assert!(tasks[0].status_history.is_empty());
+    }
+
+    #[test]
+    fn upgrades_versioned_files_without_due_times() {
+        let yaml = from_yaml(include_str!("../../tests/fixtures/tasks_v2.yaml")).unwrap();
+        let json = migrate_document(
+            serde_json::from_str(include_str!("../../tests/fixtures/tasks_v2.json")).unwrap(),
+        )
+        .unwrap();
+
+        for tasks in [yaml, json] {
+            assert_eq!(tasks[0].maximum_chunk_size, Some(60));
+            assert_eq!(tasks[0].status_history[0].to, Status::OnHold);
+            assert_eq!(tasks[0].due_time, None);
+        }
+    }
+
+    #[test]
+    fn upgrades_files_without_dependencies() {
+        let tasks = from_yaml(include_str!("../../tests/fixtures/tasks_v3.yaml")).unwrap();
+
+        assert_eq!(tasks[0].due_time, NaiveTime::from_hms_opt(17, 0, 0));
+        assert!(tasks[0].depends_on.is_empty());
+    }
+
+    #[test]
+    fn upgrades_files_without_subtasks() {
+        let tasks = from_yaml(include_str!("../../tests/fixtures/tasks_v4.yaml")).unwrap();
+
+        assert_eq!(tasks[1].depends_on, vec![tasks[0].id]);
+        assert_eq!(tasks[1].parent, None);
+    }
+
+    #[test]
+    fn upgrades_files_without_recurrence() {
+        let tasks = from_yaml(include_str!("../../tests/fixtures/tasks_v5.yaml")).unwrap();
+
+        assert_eq!(tasks[1].parent, Some(tasks[0].id));
+        assert_eq!(tasks[1].recurrence, None);
+    }
+
+    #[test]
+    fn upgrades_files_without_tags() {
+        let tasks = from_yaml(include_str!("../../tests/fixtures/tasks_v6.yaml")).unwrap();
+        let recurrence = tasks[0].recurrence.as_ref().unwrap();
+
+        assert_eq!(recurrence.frequency, Frequency::Weekly);
+        assert_eq!(recurrence.count, Some(4));
+        assert!(tasks[0].tags.is_empty());
+        assert_eq!(tasks[0].project, None);
+    }
+
+    #[test]
+    fn upgrades_files_without_notes() {
+        let tasks = from_yaml(include_str!("../../tests/fixtures/tasks_v7.yaml")).unwrap();
+
+        assert_eq!(tasks[0].tags, vec!["work", "writing"]);
+        assert_eq!(tasks[0].project.as_deref(), Some("Quarterly Report"));
+        assert_eq!(tasks[0].description, None);
+        assert!(tasks[0].links.is_empty());
+        assert!(tasks[0].annotations.is_empty());
+    }
+
+    #[test]
+    fn reads_the_current_format() {
+        let tasks = from_yaml(include_str!("../../tests/fixtures/tasks_v8.yaml")).unwrap();
+
+        assert_eq!(
+            tasks[0].description.as_deref(),
+            Some("Summary for the board.\nNumbers come from finance.")
+        );
+        assert_eq!(tasks[0].links, vec!["https://example.com/tickets/42"]);
+        assert_eq!(tasks[0].annotations[0].text, "Sent the first draft");
+    }
+
+    #[test]
+    fn rejects_files_from_a_newer_version() {
+        let input = format!("version: {}\ntasks: []\n", SCHEMA_VERSION + 1);
+
+        assert!(from_yaml(&input).is_err());
+    }
+}
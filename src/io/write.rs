@@ -1,19 +1,129 @@
 use crate::io::*;
-use crate::prelude::*;
 
-pub fn write_tasks_to_yaml(tasks: &mut Vec<Task>, file_path: &str) -> Result<()> {
-    let yaml_string = serde_yaml::to_string(&tasks)
+/// Replaces `file_path` atomically: the contents go to a temporary file in the
+/// same directory, which is flushed to disk and then renamed over the original.
+/// A crash at any point leaves either the old file or the new one, never half
+/// of each.
+pub fn write_file(file_path: impl AsRef<Path>, contents: &str) -> Result<()> {
+    let file_path = file_path.as_ref();
+    let mut temp_name = file_path.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = std::path::PathBuf::from(temp_name);
+
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, file_path)
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(Error::IO(e));
+    }
+
+    // Persist the rename itself; not every platform can open a directory
+    if let Some(parent) = file_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+fn create_parent_dir(file_path: &Path) -> Result<()> {
+    if let Some(parent) = file_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    Ok(())
+}
+
+pub fn write_tasks_to_yaml(tasks: &[Task], file_path: impl AsRef<Path>) -> Result<()> {
+    let yaml_string = serde_yaml::to_string(&TaskDocument::current(tasks))
         .map_err(|e| Error::Generic(format!("Failed to serialize tasks to YAML. Error: {}", e)))?;
 
-    // Write the updated YAML content to the file
-    std::fs::write(file_path, yaml_string).map_err(Error::IO)?;
+    write_file(file_path, &yaml_string)?;
+
+    Ok(())
+}
+
+pub fn write_tasks_to_json(tasks: &[Task], file_path: impl AsRef<Path>) -> Result<()> {
+    let json_string = serde_json::to_string_pretty(&TaskDocument::current(tasks))
+        .map_err(|e| Error::Generic(format!("Failed to serialize tasks to JSON. Error: {}", e)))?;
+
+    write_file(file_path, &json_string)?;
+
+    Ok(())
+}
+
+pub fn write_schedule_to_yaml(blocks: &[TimeBlock], file_path: impl AsRef<Path>) -> Result<()> {
+    let yaml_string = serde_yaml::to_string(blocks).map_err(|e| {
+        Error::Generic(format!(
+            "Failed to serialize schedule to YAML. Error: {}",
+            e
+        ))
+    })?;
+
+    write_file(file_path, &yaml_string)?;
+
+    Ok(())
+}
+
+pub fn write_busy_blocks_to_yaml(blocks: &[BusyBlock], file_path: impl AsRef<Path>) -> Result<()> {
+    let yaml_string = serde_yaml::to_string(blocks).map_err(|e| {
+        Error::Generic(format!(
+            "Failed to serialize busy time to YAML. Error: {}",
+            e
+        ))
+    })?;
+
+    write_file(file_path, &yaml_string)?;
+
+    Ok(())
+}
+
+pub fn write_config_to_yaml(config: &Config, file_path: impl AsRef<Path>) -> Result<()> {
+    let yaml_string = serde_yaml::to_string(config)
+        .map_err(|e| Error::Generic(format!("Failed to serialize config to YAML. Error: {}", e)))?;
+
+    write_file(file_path, &yaml_string)?;
 
     Ok(())
 }
 
+/// Creates the task file and config file if they don't exist yet, returning
+/// the ones it created. Existing files are never overwritten. The task file is
+/// created in whatever format the configured storage backend uses, or the
+/// default one if the config can't be read.
+pub fn initialize_store(paths: &DataPaths) -> Result<Vec<std::path::PathBuf>> {
+    let mut created = Vec::new();
+    let config = read_config(&paths.config).unwrap_or_else(|e| {
+        eprintln!(
+            "Warning: {} could not be read, so defaults are used; fix or remove it. {}",
+            paths.config.display(),
+            e
+        );
+        Config::default()
+    });
+
+    if !paths.tasks.exists() {
+        create_parent_dir(&paths.tasks)?;
+        open_store(config.storage.backend, &paths.tasks)?.save_all(Vec::new())?;
+        created.push(paths.tasks.clone());
+    }
+    if !paths.config.exists() {
+        create_parent_dir(&paths.config)?;
+        write_config_to_yaml(&Config::default(), &paths.config)?;
+        created.push(paths.config.clone());
+    }
+
+    Ok(created)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::sample_task;
     use std::fs;
 
     #[test]
@@ -22,35 +132,25 @@ mod tests {
         let temp_file = "test_output.yaml";
 
         // Sample tasks
-        let mut tasks = vec![
+        let tasks = vec![
             Task {
-                id: uuid::Uuid::new_v4(),
-                name: "Sample Task 1".to_string(),
                 time_remaining: 5,
-                due_date: chrono::NaiveDate::from_ymd_opt(2024, 11, 30).unwrap(),
-                status: Status::UnStarted,
-                created_date: chrono::Utc::now(),
                 priority_level: Priority::High,
                 minimum_chunk_size: Some(30),
-                elapsed_time: 0,
                 work_intervals: vec![(chrono::Utc::now(), None)],
+                ..sample_task("Sample Task 1")
             },
             Task {
-                id: uuid::Uuid::new_v4(),
-                name: "Sample Task 2".to_string(),
                 time_remaining: 8,
-                due_date: chrono::NaiveDate::from_ymd_opt(2024, 12, 1).unwrap(),
                 status: Status::InProgress,
-                created_date: chrono::Utc::now(),
                 priority_level: Priority::Urgent,
-                minimum_chunk_size: None,
-                elapsed_time: 0,
                 work_intervals: vec![(chrono::Utc::now(), None)],
+                ..sample_task("Sample Task 2")
             },
         ];
 
         // Call the function
-        let result = write_tasks_to_yaml(&mut tasks, temp_file);
+        let result = write_tasks_to_yaml(&tasks, temp_file);
         assert!(result.is_ok());
 
         // Read the written file and verify content
@@ -67,35 +167,65 @@ mod tests {
     #[test]
     fn saves_empty_tasks_to_yaml_file() {
         let temp_file = "test_empty_output.yaml";
-        let mut tasks: Vec<Task> = vec![];
+        let tasks: Vec<Task> = vec![];
 
-        let result = write_tasks_to_yaml(&mut tasks, temp_file);
+        let result = write_tasks_to_yaml(&tasks, temp_file);
         assert!(result.is_ok());
 
         let written_content =
             std::fs::read_to_string(temp_file).expect("Failed to read output file");
-        assert_eq!(written_content.trim(), "[]");
+        assert_eq!(
+            written_content.trim(),
+            format!("version: {}\ntasks: []", SCHEMA_VERSION)
+        );
 
         std::fs::remove_file(temp_file).expect("Failed to delete test file");
     }
 
+    #[test]
+    fn initializing_creates_files_once() {
+        let temp_dir = std::path::PathBuf::from("test_init_dir");
+        let paths = DataPaths::for_task_file(
+            temp_dir.join("data").join("tasks.yaml"),
+            temp_dir.join("config").join("config.yaml"),
+        );
+
+        let first = initialize_store(&paths).expect("Failed to initialize");
+        let second = initialize_store(&paths).expect("Failed to initialize again");
+        let config = read_config(&paths.config);
+
+        fs::remove_dir_all(&temp_dir).expect("Failed to delete test directory");
+        assert_eq!(first, vec![paths.tasks.clone(), paths.config.clone()]);
+        assert!(second.is_empty());
+        assert_eq!(config.expect("Failed to read config"), Config::default());
+    }
+
     #[test]
     fn returns_error_when_writing_to_invalid_path() {
-        let invalid_path = "/invalid_directory/test_output.yaml";
-        let mut tasks = vec![Task {
-            id: uuid::Uuid::new_v4(),
-            name: "Sample Task".to_string(),
-            time_remaining: 5,
-            due_date: chrono::NaiveDate::from_ymd_opt(2024, 11, 30).unwrap(),
-            status: Status::UnStarted,
-            created_date: chrono::Utc::now(),
-            priority_level: Priority::High,
-            minimum_chunk_size: None,
-            elapsed_time: 0,
-            work_intervals: vec![(chrono::Utc::now(), None)],
-        }];
-
-        let result = write_tasks_to_yaml(&mut tasks, invalid_path);
+        // A path under a regular file can never be created, whoever runs this
+        let blocker = std::env::temp_dir().join(format!("auto-cal-{}", uuid::Uuid::new_v4()));
+        fs::write(&blocker, "").expect("Failed to create test file");
+        let tasks = vec![sample_task("Sample Task")];
+
+        let result = write_tasks_to_yaml(&tasks, blocker.join("test_output.yaml"));
+
+        fs::remove_file(&blocker).expect("Failed to delete test file");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn init_works_with_a_broken_config() {
+        let temp_dir = std::env::temp_dir().join(format!("auto-cal-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&temp_dir).expect("Failed to create test directory");
+        let config = temp_dir.join("config.yaml");
+        fs::write(&config, "timezone: [oops").expect("Failed to write config");
+        let paths = DataPaths::for_task_file(temp_dir.join("tasks.yaml"), config);
+
+        let created = initialize_store(&paths);
+        let tasks_exist = paths.tasks.exists();
+
+        fs::remove_dir_all(&temp_dir).expect("Failed to delete test directory");
+        assert_eq!(created.unwrap(), vec![paths.tasks.clone()]);
+        assert!(tasks_exist);
+    }
 }
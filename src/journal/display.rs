@@ -0,0 +1,84 @@
+use crate::prelude::*;
+
+use std::fmt;
+
+impl fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OperationKind::Create => "create",
+            OperationKind::Edit => "edit",
+            OperationKind::Start => "start",
+            OperationKind::Stop => "stop",
+            OperationKind::Complete => "complete",
+            OperationKind::Annotate => "annotate",
+            OperationKind::Import => "import",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.field, self.old, self.new)
+    }
+}
+
+/// Prints what undoing (or redoing) `operation` changed, task by task.
+pub fn list_reverted_operation(operation: &Operation, undo: bool) -> Result<()> {
+    let first = operation.first();
+    let name = first
+        .after
+        .as_ref()
+        .or(first.before.as_ref())
+        .map(|task| task.name.as_str())
+        .unwrap_or_default();
+    println!(
+        "{} {} of {}",
+        if undo { "Undid" } else { "Redid" },
+        operation.kind,
+        name
+    );
+
+    for change in &operation.changes {
+        let (from, to) = if undo {
+            (change.after.as_ref(), change.before.as_ref())
+        } else {
+            (change.before.as_ref(), change.after.as_ref())
+        };
+        match (from, to) {
+            (Some(_), None) => println!("  removed task {}", change.task_id),
+            (None, Some(_)) => println!("  added task {}", change.task_id),
+            (Some(task), Some(_)) => {
+                let indent = if operation.changes.len() > 1 {
+                    println!("  {}:", task.label());
+                    "    "
+                } else {
+                    "  "
+                };
+                for field in describe_changes(from, to)? {
+                    println!("{}{}", indent, field);
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints `events` one per line, with times in `zone`.
+pub fn list_history(events: &[HistoryEvent], zone: Zone) -> Result<()> {
+    if events.is_empty() {
+        println!("Nothing recorded");
+    }
+
+    for event in events {
+        println!(
+            "{}  {}",
+            zone.local(event.at).format("%Y-%m-%d %H:%M"),
+            event.description
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,278 @@
+use crate::prelude::*;
+
+/// Fields that get events of their own, or only change alongside another
+/// field, so listing them as edits would just repeat the timeline.
+const DERIVED_FIELDS: [&str; 3] = ["status", "status_history", "work_intervals"];
+
+/// Something that happened at a point in time, ready to print.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEvent {
+    pub at: DateTime<Utc>,
+    pub description: String,
+}
+
+impl HistoryEvent {
+    fn new(at: DateTime<Utc>, description: String) -> Self {
+        HistoryEvent { at, description }
+    }
+}
+
+/// The full timeline of one task, oldest first. Creation, status changes, work
+/// sessions and notes come from the task itself, so they cover tasks older than
+/// the journal; field edits and undo/redo come from the journal. Times of day
+/// in descriptions are given in `zone`.
+pub fn task_history(
+    task: &Task,
+    entries: &[JournalEntry],
+    zone: Zone,
+) -> Result<Vec<HistoryEvent>> {
+    let mut events = vec![HistoryEvent::new(
+        task.created_date,
+        format!("Created {}", task.name),
+    )];
+
+    for change in &task.status_history {
+        events.push(HistoryEvent::new(
+            change.at,
+            format!("Status changed from {} to {}", change.from, change.to),
+        ));
+    }
+
+    for (start, end) in &task.work_intervals {
+        let description = match end {
+            Some(end) => format!(
+                "Worked {}, until {}",
+                format_duration((*end - *start).num_minutes().max(0) as u32),
+                zone.local(*end).format("%H:%M")
+            ),
+            None => "Started a work session, still in progress".to_string(),
+        };
+        events.push(HistoryEvent::new(*start, description));
+    }
+
+    for annotation in &task.annotations {
+        let first_line = annotation.text.lines().next().unwrap_or_default();
+        events.push(HistoryEvent::new(
+            annotation.at,
+            format!("Note: {}", first_line),
+        ));
+    }
+
+    let operations = operations_by_entry(entries);
+    for entry in entries {
+        match &entry.action {
+            JournalAction::Apply(operation)
+                if matches!(operation.kind, OperationKind::Edit | OperationKind::Import) =>
+            {
+                let verb = match operation.kind {
+                    OperationKind::Import => "Imported",
+                    _ => "Edited",
+                };
+                let own = operation.changes.iter().filter(|c| c.task_id == task.id);
+                for change in own.filter(|change| change.before.is_some()) {
+                    for field in edited_fields(change)? {
+                        events.push(HistoryEvent::new(entry.at, format!("{} {}", verb, field)));
+                    }
+                }
+            }
+            JournalAction::Undo { operation } | JournalAction::Redo { operation } => {
+                if let Some(undone) = operations.get(operation) {
+                    if undone.touches(task.id) {
+                        events.push(HistoryEvent::new(entry.at, describe_entry(entry, undone)?));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    events.sort_by_key(|event| event.at);
+    Ok(events)
+}
+
+/// Every journal entry between `since` and `until` (inclusive, dates in `zone`).
+pub fn journal_log(
+    entries: &[JournalEntry],
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    zone: Zone,
+) -> Result<Vec<HistoryEvent>> {
+    let operations = operations_by_entry(entries);
+    let mut events = Vec::new();
+
+    for entry in entries {
+        let date = zone.local(entry.at).date();
+        if since.is_some_and(|since| date < since) || until.is_some_and(|until| date > until) {
+            continue;
+        }
+
+        let operation = match &entry.action {
+            JournalAction::Apply(operation) => Some(operation.as_ref()),
+            JournalAction::Undo { operation } | JournalAction::Redo { operation } => {
+                operations.get(operation).copied()
+            }
+        };
+        if let Some(operation) = operation {
+            events.push(HistoryEvent::new(
+                entry.at,
+                describe_entry(entry, operation)?,
+            ));
+        }
+    }
+
+    Ok(events)
+}
+
+/// The most recent snapshot of the task whose id starts with `prefix`, for
+/// tasks that are no longer in the store.
+pub fn find_journaled_task(entries: &[JournalEntry], prefix: &str) -> Option<Task> {
+    let mut found: Option<Task> = None;
+
+    for entry in entries {
+        let JournalAction::Apply(operation) = &entry.action else {
+            continue;
+        };
+        for change in &operation.changes {
+            if !change.task_id.to_string().starts_with(prefix) {
+                continue;
+            }
+            if found.as_ref().is_some_and(|task| task.id != change.task_id) {
+                return None;
+            }
+            if let Some(task) = change.after.as_ref().or(change.before.as_ref()) {
+                found = Some(task.clone());
+            }
+        }
+    }
+
+    found
+}
+
+fn operations_by_entry(entries: &[JournalEntry]) -> std::collections::HashMap<Uuid, &Operation> {
+    entries
+        .iter()
+        .filter_map(|entry| match &entry.action {
+            JournalAction::Apply(operation) => Some((entry.id, operation.as_ref())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn edited_fields(change: &TaskChange) -> Result<Vec<FieldChange>> {
+    Ok(
+        describe_changes(change.before.as_ref(), change.after.as_ref())?
+            .into_iter()
+            .filter(|change| !DERIVED_FIELDS.contains(&change.field.as_str()))
+            .collect(),
+    )
+}
+
+fn describe_entry(entry: &JournalEntry, operation: &Operation) -> Result<String> {
+    let first = operation.first();
+    let name = first
+        .after
+        .as_ref()
+        .or(first.before.as_ref())
+        .map(|task| task.name.as_str())
+        .unwrap_or_default();
+    let prefix = match entry.action {
+        JournalAction::Apply(_) => "",
+        JournalAction::Undo { .. } => "Undid ",
+        JournalAction::Redo { .. } => "Redid ",
+    };
+    let mut description = format!(
+        "{}{} {} ({})",
+        prefix,
+        operation.kind,
+        name,
+        &first.task_id.to_string()[..8]
+    );
+    if operation.changes.len() > 1 {
+        description.push_str(&format!(" and {} more", operation.changes.len() - 1));
+    }
+
+    let changes = match operation.kind {
+        OperationKind::Edit => edited_fields(first)?,
+        _ => Vec::new(),
+    };
+    if !changes.is_empty() {
+        let changes: Vec<String> = changes.iter().map(ToString::to_string).collect();
+        description.push_str(&format!(": {}", changes.join(", ")));
+    }
+
+    Ok(description)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    #[test]
+    fn builds_a_timeline_from_the_task_and_journal() {
+        let temp_file = std::path::PathBuf::from("test_history.jsonl");
+        let journal = Journal::new(&temp_file);
+        let created = sample_task("report");
+        let mut edited = created.clone();
+        edited.priority_level = Priority::Urgent;
+        let mut started = edited.clone();
+        started.start_work().unwrap();
+        started.stop_work().unwrap();
+        started
+            .annotate("Asked Sam for figures\nThey reply Monday")
+            .unwrap();
+
+        journal
+            .record(OperationKind::Create, None, Some(&created))
+            .unwrap();
+        journal
+            .record(OperationKind::Edit, Some(&created), Some(&edited))
+            .unwrap();
+        let entries = journal.entries().unwrap();
+        let history = task_history(&started, &entries, Zone::Local).unwrap();
+        let other = task_history(&sample_task("other"), &entries, Zone::Local).unwrap();
+
+        std::fs::remove_file(&temp_file).expect("Failed to delete test file");
+        let descriptions: Vec<&str> = history.iter().map(|e| e.description.as_str()).collect();
+        assert_eq!(descriptions[0], "Created report");
+        assert!(descriptions.contains(&"Edited priority_level: \"Medium\" -> \"Urgent\""));
+        assert!(descriptions.contains(&"Status changed from UnStarted to InProgress"));
+        assert!(descriptions.contains(&"Status changed from InProgress to OnHold"));
+        assert!(descriptions.iter().any(|d| d.starts_with("Worked 0m")));
+        assert!(descriptions.contains(&"Note: Asked Sam for figures"));
+        assert_eq!(other.len(), 1);
+    }
+
+    #[test]
+    fn filters_the_log_by_date() {
+        let task = sample_task("report");
+        let entry = |days: i64| JournalEntry {
+            id: Uuid::new_v4(),
+            at: Utc::now() - chrono::Duration::days(days),
+            action: JournalAction::Apply(Box::new(Operation {
+                kind: OperationKind::Create,
+                changes: vec![TaskChange::new(None, Some(&task)).unwrap()],
+            })),
+        };
+        let entries = vec![entry(10), entry(5), entry(0)];
+        let today = Zone::Local.today();
+
+        let recent = journal_log(
+            &entries,
+            Some(today - chrono::Duration::days(6)),
+            None,
+            Zone::Local,
+        )
+        .unwrap();
+        let older = journal_log(
+            &entries,
+            None,
+            Some(today - chrono::Duration::days(6)),
+            Zone::Local,
+        )
+        .unwrap();
+
+        assert_eq!(recent.len(), 2);
+        assert_eq!(older.len(), 1);
+    }
+}
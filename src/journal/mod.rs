@@ -0,0 +1,487 @@
+use crate::io::*;
+use crate::prelude::*;
+use serde_json::Value;
+use std::io::{Seek, SeekFrom};
+use std::path::PathBuf;
+
+pub mod display;
+pub mod history;
+
+/// The commands whose changes can be undone.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Create,
+    Edit,
+    Start,
+    Stop,
+    Complete,
+    Annotate,
+    Import,
+}
+
+/// One task as it was before and after a change. `before` is empty for a
+/// created task.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskChange {
+    pub task_id: Uuid,
+    pub before: Option<Task>,
+    pub after: Option<Task>,
+}
+
+impl TaskChange {
+    pub fn new(before: Option<&Task>, after: Option<&Task>) -> Result<Self> {
+        let task_id = match (before, after) {
+            (_, Some(task)) | (Some(task), None) => task.id,
+            (None, None) => return Err(Error::Generic("Nothing to record".to_string())),
+        };
+        Ok(TaskChange {
+            task_id,
+            before: before.cloned(),
+            after: after.cloned(),
+        })
+    }
+}
+
+/// Everything one command changed, undone and redone as a whole. The first
+/// change is the task the command was run on; the rest are follow-on changes
+/// such as the next occurrence of a repeating task.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub changes: Vec<TaskChange>,
+}
+
+impl Operation {
+    pub fn first(&self) -> &TaskChange {
+        &self.changes[0]
+    }
+
+    pub fn touches(&self, task_id: Uuid) -> bool {
+        self.changes.iter().any(|change| change.task_id == task_id)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum JournalAction {
+    Apply(Box<Operation>),
+    Undo { operation: Uuid },
+    Redo { operation: Uuid },
+}
+
+/// Operations paired with the id of the journal entry that applied them.
+pub type OperationStack = Vec<(Uuid, Operation)>;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JournalEntry {
+    pub id: Uuid,
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub action: JournalAction,
+}
+
+/// Append-only log of every change made through the CLI, one JSON entry per
+/// line. Undo and redo are logged as entries of their own rather than by
+/// rewriting history, so the file only ever grows.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn new(path: &Path) -> Self {
+        Journal {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// Every entry in the journal, oldest first. A crash while appending can
+    /// leave the last line cut short; it is skipped with a warning, while a
+    /// malformed line anywhere else is an error.
+    pub fn entries(&self) -> Result<Vec<JournalEntry>> {
+        let contents = read_to_string_if_exists(&self.path)?.unwrap_or_default();
+
+        let mut entries = Vec::new();
+        let mut lines = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .peekable();
+        while let Some((index, line)) = lines.next() {
+            match serde_json::from_str(line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) if lines.peek().is_none() => eprintln!(
+                    "Warning: skipped the incomplete last entry of {} (line {}): {}",
+                    self.path.display(),
+                    index + 1,
+                    e
+                ),
+                Err(e) => {
+                    return Err(Error::Generic(format!(
+                        "Failed to deserialize journal entry on line {} of {}. Error: {}",
+                        index + 1,
+                        self.path.display(),
+                        e
+                    )))
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    fn append(&self, action: JournalAction) -> Result<JournalEntry> {
+        let entry = JournalEntry {
+            id: Uuid::new_v4(),
+            at: Utc::now(),
+            action,
+        };
+        let line = serde_json::to_string(&entry).map_err(|e| {
+            Error::Generic(format!("Failed to serialize journal entry. Error: {}", e))
+        })?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+        self.drop_incomplete_entry(&mut file)?;
+        file.write_all(format!("{}\n", line).as_bytes())?;
+        file.sync_all()?;
+
+        Ok(entry)
+    }
+
+    /// Cuts off a last line that was never finished, so the next entry
+    /// doesn't get appended onto it.
+    fn drop_incomplete_entry(&self, file: &mut File) -> Result<()> {
+        if file.metadata()?.len() == 0 {
+            return Ok(());
+        }
+        let mut last = [0u8];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+        if last[0] == b'\n' {
+            return Ok(());
+        }
+
+        let mut contents = Vec::new();
+        file.seek(SeekFrom::Start(0))?;
+        file.read_to_end(&mut contents)?;
+        let complete = contents
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |newline| newline + 1);
+        file.set_len(complete as u64)?;
+        eprintln!(
+            "Warning: dropped an incomplete entry at the end of {}",
+            self.path.display()
+        );
+        Ok(())
+    }
+
+    pub fn record(
+        &self,
+        kind: OperationKind,
+        before: Option<&Task>,
+        after: Option<&Task>,
+    ) -> Result<()> {
+        self.record_all(kind, vec![TaskChange::new(before, after)?])
+    }
+
+    /// Records several changes made by one command as a single operation.
+    pub fn record_all(&self, kind: OperationKind, changes: Vec<TaskChange>) -> Result<()> {
+        if changes.is_empty() {
+            return Err(Error::Generic("Nothing to record".to_string()));
+        }
+        self.append(JournalAction::Apply(Box::new(Operation { kind, changes })))?;
+        Ok(())
+    }
+
+    /// Operations that can currently be undone and redone, most recent last.
+    pub fn stacks(&self) -> Result<(OperationStack, OperationStack)> {
+        let mut done = OperationStack::new();
+        let mut undone = OperationStack::new();
+
+        for entry in self.entries()? {
+            match entry.action {
+                JournalAction::Apply(operation) => {
+                    done.push((entry.id, *operation));
+                    undone.clear();
+                }
+                JournalAction::Undo { operation } => {
+                    if let Some(index) = done.iter().rposition(|(id, _)| *id == operation) {
+                        undone.push(done.remove(index));
+                    }
+                }
+                JournalAction::Redo { operation } => {
+                    if let Some(index) = undone.iter().rposition(|(id, _)| *id == operation) {
+                        done.push(undone.remove(index));
+                    }
+                }
+            }
+        }
+
+        Ok((done, undone))
+    }
+
+    /// Reverts the most recent operation that hasn't been undone yet.
+    pub fn undo(&self, store: &mut dyn TaskStore) -> Result<Option<Operation>> {
+        let (mut done, _) = self.stacks()?;
+        let Some((id, operation)) = done.pop() else {
+            return Ok(None);
+        };
+
+        let reverted = operation.changes.iter().rev().map(|change| {
+            (
+                change.task_id,
+                change.after.as_ref(),
+                change.before.as_ref(),
+            )
+        });
+        replace_tasks(store, reverted.collect())?;
+        self.append(JournalAction::Undo { operation: id })?;
+        Ok(Some(operation))
+    }
+
+    /// Reapplies the most recently undone operation.
+    pub fn redo(&self, store: &mut dyn TaskStore) -> Result<Option<Operation>> {
+        let (_, mut undone) = self.stacks()?;
+        let Some((id, operation)) = undone.pop() else {
+            return Ok(None);
+        };
+
+        let reapplied = operation.changes.iter().map(|change| {
+            (
+                change.task_id,
+                change.before.as_ref(),
+                change.after.as_ref(),
+            )
+        });
+        replace_tasks(store, reapplied.collect())?;
+        self.append(JournalAction::Redo { operation: id })?;
+        Ok(Some(operation))
+    }
+}
+
+/// Swaps each `(task_id, expected, replacement)` in the store, in order.
+/// Changes that didn't go through the journal, like restores, can leave a
+/// task in some other state; nothing is touched then rather than silently
+/// overwritten.
+fn replace_tasks(
+    store: &mut dyn TaskStore,
+    replacements: Vec<(Uuid, Option<&Task>, Option<&Task>)>,
+) -> Result<()> {
+    for (task_id, expected, _) in &replacements {
+        let current = store.get(task_id)?;
+        if to_value(current.as_ref())? != to_value(*expected)? {
+            return Err(Error::Generic(format!(
+                "Task {} has changed since this operation; not overwriting it",
+                task_id
+            )));
+        }
+    }
+
+    for (task_id, expected, replacement) in replacements {
+        match (expected, replacement) {
+            (Some(_), Some(task)) => store.update(task.clone())?,
+            (Some(_), None) => store.delete(&task_id)?,
+            (None, Some(task)) => store.insert(task.clone())?,
+            (None, None) => {}
+        }
+    }
+    Ok(())
+}
+
+fn to_value(task: Option<&Task>) -> Result<Value> {
+    serde_json::to_value(task)
+        .map_err(|e| Error::Generic(format!("Failed to serialize task. Error: {}", e)))
+}
+
+/// A field whose value differs between two versions of a task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// Every field that differs between `before` and `after`, in field order.
+pub fn describe_changes(before: Option<&Task>, after: Option<&Task>) -> Result<Vec<FieldChange>> {
+    let before = to_value(before)?;
+    let after = to_value(after)?;
+    let (Value::Object(before), Value::Object(after)) = (&before, &after) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(after
+        .iter()
+        .filter(|(field, value)| before.get(field.as_str()) != Some(*value))
+        .map(|(field, value)| FieldChange {
+            field: field.clone(),
+            old: before.get(field.as_str()).cloned().unwrap_or(Value::Null),
+            new: value.clone(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    #[test]
+    fn undoes_and_redoes_in_order() {
+        let temp_file = PathBuf::from("test_journal.jsonl");
+        let journal = Journal::new(&temp_file);
+        let mut store = SqliteStore::in_memory().unwrap();
+
+        let created = sample_task("draft");
+        store.insert(created.clone()).unwrap();
+        journal
+            .record(OperationKind::Create, None, Some(&created))
+            .unwrap();
+        let mut edited = created.clone();
+        edited.name = "final".to_string();
+        store.update(edited.clone()).unwrap();
+        journal
+            .record(OperationKind::Edit, Some(&created), Some(&edited))
+            .unwrap();
+
+        let first = journal.undo(&mut store).unwrap().unwrap();
+        let name_after_undo = store.get(&created.id).unwrap().unwrap().name;
+        let second = journal.undo(&mut store).unwrap().unwrap();
+        let gone = store.get(&created.id).unwrap().is_none();
+        let nothing_left = journal.undo(&mut store).unwrap().is_none();
+        journal.redo(&mut store).unwrap();
+        let redone = store.get(&created.id).unwrap().unwrap().name;
+
+        std::fs::remove_file(&temp_file).expect("Failed to delete test file");
+        assert_eq!(first.kind, OperationKind::Edit);
+        assert_eq!(name_after_undo, "draft");
+        assert_eq!(second.kind, OperationKind::Create);
+        assert!(gone);
+        assert!(nothing_left);
+        assert_eq!(redone, "draft");
+    }
+
+    #[test]
+    fn new_operations_clear_the_redo_stack() {
+        let temp_file = PathBuf::from("test_journal_redo.jsonl");
+        let journal = Journal::new(&temp_file);
+        let mut store = SqliteStore::in_memory().unwrap();
+
+        let task = sample_task("one");
+        store.insert(task.clone()).unwrap();
+        journal
+            .record(OperationKind::Create, None, Some(&task))
+            .unwrap();
+        journal.undo(&mut store).unwrap();
+        let other = sample_task("two");
+        store.insert(other.clone()).unwrap();
+        journal
+            .record(OperationKind::Create, None, Some(&other))
+            .unwrap();
+
+        let redone = journal.redo(&mut store).unwrap();
+
+        std::fs::remove_file(&temp_file).expect("Failed to delete test file");
+        assert!(redone.is_none());
+    }
+
+    #[test]
+    fn refuses_to_undo_over_outside_changes() {
+        let temp_file = PathBuf::from("test_journal_conflict.jsonl");
+        let journal = Journal::new(&temp_file);
+        let mut store = SqliteStore::in_memory().unwrap();
+
+        let task = sample_task("one");
+        store.insert(task.clone()).unwrap();
+        journal
+            .record(OperationKind::Create, None, Some(&task))
+            .unwrap();
+        let mut changed = task.clone();
+        changed.time_remaining = 5;
+        store.update(changed).unwrap();
+
+        let result = journal.undo(&mut store);
+
+        std::fs::remove_file(&temp_file).expect("Failed to delete test file");
+        assert!(result.is_err());
+        assert_eq!(store.get(&task.id).unwrap().unwrap().time_remaining, 5);
+    }
+
+    #[test]
+    fn undoes_every_change_of_a_grouped_operation() {
+        let temp_file = PathBuf::from("test_journal_grouped.jsonl");
+        let journal = Journal::new(&temp_file);
+        let mut store = SqliteStore::in_memory().unwrap();
+
+        let task = sample_task("weekly review");
+        store.insert(task.clone()).unwrap();
+        let mut completed = task.clone();
+        completed.status = Status::Completed;
+        store.update(completed.clone()).unwrap();
+        let next = sample_task("weekly review");
+        store.insert(next.clone()).unwrap();
+        journal
+            .record_all(
+                OperationKind::Complete,
+                vec![
+                    TaskChange::new(Some(&task), Some(&completed)).unwrap(),
+                    TaskChange::new(None, Some(&next)).unwrap(),
+                ],
+            )
+            .unwrap();
+
+        journal.undo(&mut store).unwrap();
+        let status_after_undo = store.get(&task.id).unwrap().unwrap().status;
+        let next_after_undo = store.get(&next.id).unwrap();
+        journal.redo(&mut store).unwrap();
+        let status_after_redo = store.get(&task.id).unwrap().unwrap().status;
+        let next_after_redo = store.get(&next.id).unwrap();
+
+        std::fs::remove_file(&temp_file).expect("Failed to delete test file");
+        assert_eq!(status_after_undo, task.status);
+        assert!(next_after_undo.is_none());
+        assert_eq!(status_after_redo, Status::Completed);
+        assert!(next_after_redo.is_some());
+    }
+
+    #[test]
+    fn skips_an_incomplete_last_entry() {
+        let temp_file = PathBuf::from("test_journal_truncated.jsonl");
+        let journal = Journal::new(&temp_file);
+        let task = sample_task("one");
+        journal
+            .record(OperationKind::Create, None, Some(&task))
+            .unwrap();
+        let mut file = OpenOptions::new().append(true).open(&temp_file).unwrap();
+        file.write_all(b"{\"id\":\"0b1c").unwrap();
+
+        let before_append = journal.entries().unwrap().len();
+        journal
+            .record(OperationKind::Create, None, Some(&sample_task("two")))
+            .unwrap();
+        let after_append = journal.entries().unwrap().len();
+        std::fs::write(&temp_file, "{}\n{}\n").unwrap();
+        let corrupted = journal.entries();
+
+        std::fs::remove_file(&temp_file).expect("Failed to delete test file");
+        assert_eq!(before_append, 1);
+        assert_eq!(after_append, 2);
+        assert!(corrupted.is_err());
+    }
+
+    #[test]
+    fn describes_changed_fields() {
+        let before = sample_task("old");
+        let mut after = before.clone();
+        after.name = "new".to_string();
+
+        let changes = describe_changes(Some(&before), Some(&after)).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].to_string(), "name: \"old\" -> \"new\"");
+    }
+}
@@ -1,32 +1,114 @@
-#![allow(unused)] // For beginning only.
-
 use crate::cli::{Cli, Command};
 use crate::command_handlers::*;
 use crate::prelude::*;
 
 mod cli;
 mod command_handlers;
+mod config;
 mod error;
+mod ical;
 mod io;
+mod journal;
 mod prelude;
+mod schedule;
+mod store;
 mod task;
+#[cfg(test)]
+mod test_support;
 mod utils;
 
-fn main() -> Result<()> {
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
     let cli = Cli::parse_cli(); // Use the parse_cli function
+                                // init creates the config, so it mustn't depend on reading one
+    let paths = match cli.command {
+        Command::Init => DataPaths::resolve_for_init(cli.file)?,
+        _ => DataPaths::resolve(cli.file)?,
+    };
 
     match cli.command {
+        Command::Init => handle_init(&paths)?,
         Command::Create {
             name,
             time,
             due_date,
             priority,
             chunk_size,
-        } => handle_create(name, time, due_date, priority, chunk_size)?,
-        Command::List { all, count } => handle_list(all, count)?,
-        Command::Start { id } => handle_start(id)?,
-        Command::Stop { id } => handle_stop(id)?,
-        Command::Complete { id } => handle_complete(id)?,
+            max_chunk_size,
+            depends_on,
+            parent,
+            repeat,
+            repeat_count,
+            repeat_until,
+            tag,
+            project,
+            description,
+            link,
+        } => handle_create(
+            &paths,
+            TaskCreatePayload {
+                name,
+                time_remaining: time,
+                due_date,
+                priority,
+                minimum_chunk_size: chunk_size,
+                maximum_chunk_size: max_chunk_size,
+                depends_on,
+                parent,
+                repeat,
+                repeat_count,
+                repeat_until,
+                tags: tag,
+                project,
+                description,
+                links: link,
+            },
+        )?,
+        Command::List {
+            all,
+            count,
+            tag,
+            exclude_tag,
+            project,
+            exclude_project,
+        } => handle_list(
+            &paths,
+            all,
+            count,
+            TaskQuery {
+                tags: normalize_tags(&tag)?,
+                exclude_tags: normalize_tags(&exclude_tag)?,
+                projects: normalize_projects(&project)?,
+                exclude_projects: normalize_projects(&exclude_project)?,
+                ..TaskQuery::default()
+            },
+        )?,
+        Command::Start { id } => handle_start(&paths, id)?,
+        Command::Stop { id } => handle_stop(&paths, id)?,
+        Command::Complete { id } => handle_complete(&paths, id)?,
+        Command::Annotate { id, text } => handle_annotate(&paths, id, text)?,
+        Command::Show { id } => handle_show(&paths, id)?,
+        Command::Schedule { show } => handle_schedule(&paths, show)?,
+        Command::Check => handle_check(&paths)?,
+        Command::ImportBusy { path } => handle_import_busy(&paths, path)?,
+        Command::Export {
+            format,
+            output,
+            todos,
+            all,
+        } => handle_export(&paths, format, output, todos, all)?,
+        Command::ImportTodos { path } => handle_import_todos(&paths, path)?,
+        Command::Restore { backup, list } => handle_restore(&paths, backup, list)?,
+        Command::Undo { steps } => handle_undo(&paths, steps)?,
+        Command::Redo { steps } => handle_redo(&paths, steps)?,
+        Command::History { id } => handle_history(&paths, id)?,
+        Command::Log { since, until } => handle_log(&paths, since, until)?,
         Command::Edit {
             id,
             name,
@@ -35,8 +117,26 @@ fn main() -> Result<()> {
             status,
             priority,
             chunk_size,
+            max_chunk_size,
             elapsed_time,
+            depends_on,
+            remove_dependency,
+            parent,
+            no_parent,
+            repeat,
+            repeat_count,
+            repeat_until,
+            no_repeat,
+            tag,
+            remove_tag,
+            project,
+            no_project,
+            description,
+            edit_description,
+            link,
+            remove_link,
         } => handle_edit(
+            &paths,
             id,
             TaskEditPayload {
                 name,
@@ -45,7 +145,24 @@ fn main() -> Result<()> {
                 status,
                 priority,
                 minimum_chunk_size: chunk_size,
+                maximum_chunk_size: max_chunk_size,
                 elapsed_time,
+                add_dependencies: depends_on,
+                remove_dependencies: remove_dependency,
+                parent,
+                remove_parent: no_parent,
+                repeat,
+                repeat_count,
+                repeat_until,
+                remove_repeat: no_repeat,
+                add_tags: tag,
+                remove_tags: remove_tag,
+                project,
+                remove_project: no_project,
+                description,
+                edit_description,
+                add_links: link,
+                remove_links: remove_link,
             },
         )?,
     }
@@ -4,17 +4,47 @@ pub use crate::error::Error;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
-// Generic Wrapper tuple strcut for newtype pattern
-pub struct W<T>(pub T);
-
-pub use chrono::{DateTime, NaiveDate, Utc};
+pub use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 pub use serde::{Deserialize, Serialize};
 pub use uuid::Uuid;
 
 pub use crate::task::create::*;
+pub use crate::task::dependency::*;
 pub use crate::task::display::*;
 pub use crate::task::edit::*;
+pub use crate::task::note::*;
+pub use crate::task::recurrence::*;
+pub use crate::task::status::*;
+pub use crate::task::subtask::*;
+pub use crate::task::tag::*;
 pub use crate::task::*;
 
+pub use crate::schedule::availability::*;
+pub use crate::schedule::chunk::*;
+pub use crate::schedule::display::*;
+pub use crate::schedule::feasibility::*;
+pub use crate::schedule::*;
+
+pub use crate::config::*;
+
+pub use crate::utils::date::*;
+pub use crate::utils::duration::*;
+pub use crate::utils::editor::*;
+pub use crate::utils::timezone::*;
+
+pub use crate::ical::busy::*;
+pub use crate::ical::export::*;
+pub use crate::ical::todo::*;
+
+pub use crate::io::backup::*;
+pub use crate::io::lock::*;
+pub use crate::io::paths::*;
 pub use crate::io::read::*;
+pub use crate::io::schema::*;
 pub use crate::io::write::*;
+
+pub use crate::store::*;
+
+pub use crate::journal::display::*;
+pub use crate::journal::history::*;
+pub use crate::journal::*;
@@ -0,0 +1,313 @@
+use crate::prelude::*;
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Weekday};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A single working window within a day, written as `"09:00-12:00"` in the config file.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Result<Self> {
+        if start >= end {
+            return Err(Error::Generic(format!(
+                "Invalid working window: {} must start before it ends",
+                TimeWindow { start, end }
+            )));
+        }
+        Ok(TimeWindow { start, end })
+    }
+
+    fn hm(hour: u32, minute: u32, end_hour: u32, end_minute: u32) -> Self {
+        TimeWindow {
+            start: NaiveTime::from_hms_opt(hour, minute, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(end_hour, end_minute, 0).unwrap(),
+        }
+    }
+}
+
+impl FromStr for TimeWindow {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse_time = |t: &str| {
+            NaiveTime::parse_from_str(t.trim(), "%H:%M").map_err(|_| {
+                Error::Generic(format!(
+                    "Could not parse working window: {}. Expected format: HH:MM-HH:MM",
+                    s
+                ))
+            })
+        };
+
+        match s.split_once('-') {
+            Some((start, end)) => TimeWindow::new(parse_time(start)?, parse_time(end)?),
+            None => Err(Error::Generic(format!(
+                "Could not parse working window: {}. Expected format: HH:MM-HH:MM",
+                s
+            ))),
+        }
+    }
+}
+
+impl TryFrom<String> for TimeWindow {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<TimeWindow> for String {
+    fn from(window: TimeWindow) -> Self {
+        window.to_string()
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct WeeklyHours {
+    pub monday: Vec<TimeWindow>,
+    pub tuesday: Vec<TimeWindow>,
+    pub wednesday: Vec<TimeWindow>,
+    pub thursday: Vec<TimeWindow>,
+    pub friday: Vec<TimeWindow>,
+    pub saturday: Vec<TimeWindow>,
+    pub sunday: Vec<TimeWindow>,
+}
+
+impl WeeklyHours {
+    pub fn windows_for(&self, weekday: Weekday) -> &[TimeWindow] {
+        match weekday {
+            Weekday::Mon => &self.monday,
+            Weekday::Tue => &self.tuesday,
+            Weekday::Wed => &self.wednesday,
+            Weekday::Thu => &self.thursday,
+            Weekday::Fri => &self.friday,
+            Weekday::Sat => &self.saturday,
+            Weekday::Sun => &self.sunday,
+        }
+    }
+}
+
+/// Time already committed elsewhere, e.g. meetings imported from a calendar.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BusyBlock {
+    /// Where the block came from, so re-importing a calendar replaces its old blocks
+    pub source: String,
+    pub summary: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+/// When work can be scheduled: a repeating weekly pattern plus per-date
+/// overrides, which replace the weekly windows for that day entirely.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Availability {
+    pub weekly: WeeklyHours,
+    pub overrides: BTreeMap<NaiveDate, Vec<TimeWindow>>,
+}
+
+impl Default for Availability {
+    fn default() -> Self {
+        let workday = vec![TimeWindow::hm(9, 0, 12, 0), TimeWindow::hm(13, 0, 17, 30)];
+        Availability {
+            weekly: WeeklyHours {
+                monday: workday.clone(),
+                tuesday: workday.clone(),
+                wednesday: workday.clone(),
+                thursday: workday.clone(),
+                friday: workday,
+                saturday: Vec::new(),
+                sunday: Vec::new(),
+            },
+            overrides: BTreeMap::new(),
+        }
+    }
+}
+
+impl Availability {
+    pub fn windows_on(&self, date: NaiveDate) -> &[TimeWindow] {
+        self.overrides
+            .get(&date)
+            .map(Vec::as_slice)
+            .unwrap_or_else(|| self.weekly.windows_for(date.weekday()))
+    }
+
+    /// Free windows between `from` and `from + days`, clipped so nothing starts in the past.
+    /// Overlapping or touching windows on the same day are merged into one, so
+    /// no minute is handed out twice.
+    pub fn free_slots(
+        &self,
+        from: NaiveDateTime,
+        days: i64,
+    ) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+        let mut slots = Vec::new();
+
+        for offset in 0..days {
+            let date = from.date() + Duration::days(offset);
+
+            let mut windows = self.windows_on(date).to_vec();
+            windows.sort_by_key(|window| window.start);
+
+            let mut merged: Vec<TimeWindow> = Vec::with_capacity(windows.len());
+            for window in windows {
+                match merged.last_mut() {
+                    Some(last) if window.start <= last.end => last.end = last.end.max(window.end),
+                    _ => merged.push(window),
+                }
+            }
+
+            for window in merged {
+                let start = date.and_time(window.start).max(from);
+                let end = date.and_time(window.end);
+                if start < end {
+                    slots.push((start, end));
+                }
+            }
+        }
+
+        slots
+    }
+
+    /// Like `free_slots`, with every busy block cut out.
+    pub fn free_time(
+        &self,
+        from: NaiveDateTime,
+        days: i64,
+        busy: &[BusyBlock],
+    ) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+        let mut slots = self.free_slots(from, days);
+
+        for block in busy {
+            slots = slots
+                .into_iter()
+                .flat_map(|(start, end)| {
+                    if block.end <= start || block.start >= end {
+                        return vec![(start, end)];
+                    }
+                    [(start, block.start), (block.end, end)]
+                        .into_iter()
+                        .filter(|(start, end)| start < end)
+                        .collect()
+                })
+                .collect();
+        }
+
+        slots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_availability_from_yaml() {
+        let yaml = r#"
+weekly:
+  monday: ["09:00-12:00", "13:00-17:30"]
+  saturday: ["10:00-12:00"]
+overrides:
+  2024-12-02: []
+"#;
+        let availability: Availability = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(availability.weekly.monday.len(), 2);
+        assert!(availability.weekly.tuesday.is_empty());
+        assert_eq!(availability.weekly.saturday[0].to_string(), "10:00-12:00");
+
+        // 2024-12-02 is a Monday, but the override clears it
+        let monday = NaiveDate::from_ymd_opt(2024, 12, 2).unwrap();
+        assert!(availability.windows_on(monday).is_empty());
+        assert_eq!(availability.windows_on(monday + Duration::days(7)).len(), 2);
+    }
+
+    #[test]
+    fn rejects_malformed_windows() {
+        assert!("17:00-09:00".parse::<TimeWindow>().is_err());
+        assert!("9am-5pm".parse::<TimeWindow>().is_err());
+        assert!("09:00".parse::<TimeWindow>().is_err());
+    }
+
+    #[test]
+    fn free_slots_are_clipped_to_the_starting_instant() {
+        let from = NaiveDate::from_ymd_opt(2024, 12, 2)
+            .unwrap()
+            .and_hms_opt(10, 30, 0)
+            .unwrap();
+
+        let slots = Availability::default().free_slots(from, 1);
+
+        assert_eq!(slots.len(), 2);
+        assert_eq!(slots[0].0, from);
+        assert_eq!(
+            slots[0].1.time(),
+            NaiveTime::from_hms_opt(12, 0, 0).unwrap()
+        );
+        assert_eq!(
+            slots[1].0.time(),
+            NaiveTime::from_hms_opt(13, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn overlapping_windows_are_merged() {
+        let monday = NaiveDate::from_ymd_opt(2024, 12, 2).unwrap();
+        let at = |hour, minute| monday.and_hms_opt(hour, minute, 0).unwrap();
+        let mut availability = Availability::default();
+        availability.overrides.insert(
+            monday,
+            vec![
+                TimeWindow::hm(13, 0, 15, 0),
+                TimeWindow::hm(9, 0, 12, 0),
+                TimeWindow::hm(11, 0, 12, 30),
+                TimeWindow::hm(12, 30, 13, 0),
+                TimeWindow::hm(16, 0, 17, 0),
+            ],
+        );
+
+        let slots = availability.free_slots(at(0, 0), 1);
+
+        assert_eq!(slots, vec![(at(9, 0), at(15, 0)), (at(16, 0), at(17, 0))]);
+    }
+
+    #[test]
+    fn busy_blocks_are_cut_out_of_free_time() {
+        let monday = NaiveDate::from_ymd_opt(2024, 12, 2).unwrap();
+        let at = |hour, minute| monday.and_hms_opt(hour, minute, 0).unwrap();
+        let busy = vec![BusyBlock {
+            source: "work.ics".to_string(),
+            summary: "Standup".to_string(),
+            start: at(9, 30),
+            end: at(10, 0),
+        }];
+
+        let slots = Availability::default().free_time(at(0, 0), 1, &busy);
+
+        assert_eq!(
+            slots,
+            vec![
+                (at(9, 0), at(9, 30)),
+                (at(10, 0), at(12, 0)),
+                (at(13, 0), at(17, 30))
+            ]
+        );
+    }
+}
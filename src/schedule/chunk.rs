@@ -0,0 +1,107 @@
+/// Size of the next work session for a task with `remaining` minutes left,
+/// given a free window of `available` minutes, or `None` if the window can't
+/// hold a session without breaking the chunk limits.
+///
+/// Sessions are never shorter than `min` (unless the whole task is), and a
+/// session is shortened rather than leave a remainder smaller than `min`
+/// behind. When `min` and `max` can't both be honoured, `min` wins.
+pub fn next_chunk(
+    remaining: u32,
+    available: u32,
+    min: Option<u32>,
+    max: Option<u32>,
+) -> Option<u32> {
+    if remaining == 0 {
+        return None;
+    }
+
+    let min = min.unwrap_or(0);
+    let smallest = min.min(remaining).max(1);
+    let largest = max.unwrap_or(u32::MAX).max(smallest);
+
+    let mut size = remaining.min(largest).min(available);
+    if size < smallest {
+        return None;
+    }
+
+    let leftover = remaining - size;
+    if leftover > 0 && leftover < min {
+        size = remaining - min;
+        if size < smallest {
+            // No split works, so take the whole task in one go if it fits
+            return (available >= remaining).then_some(remaining);
+        }
+    }
+
+    Some(size)
+}
+
+/// Splits `total` minutes into sessions of at least `min` minutes, folding any
+/// remainder into the sessions rather than leaving a short one at the end.
+pub fn split_into_chunks(total: u32, min: Option<u32>) -> Vec<u32> {
+    if total == 0 {
+        return Vec::new();
+    }
+
+    let count = match min {
+        Some(min) if min > 0 => (total / min).max(1),
+        _ => 1,
+    };
+
+    let base = total / count;
+    let extra = total % count;
+    (0..count).map(|i| base + u32::from(i < extra)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_chunk_handles_empty_tasks() {
+        assert_eq!(next_chunk(0, 60, Some(30), None), None);
+    }
+
+    #[test]
+    fn next_chunk_fills_the_window_without_limits() {
+        assert_eq!(next_chunk(120, 45, None, None), Some(45));
+        assert_eq!(next_chunk(30, 45, None, None), Some(30));
+    }
+
+    #[test]
+    fn next_chunk_respects_minimum_and_maximum() {
+        assert_eq!(next_chunk(120, 20, Some(30), None), None);
+        assert_eq!(next_chunk(120, 240, Some(30), Some(60)), Some(60));
+    }
+
+    #[test]
+    fn next_chunk_never_leaves_a_tiny_remainder() {
+        // 40 of 50 would strand 10 minutes below the 30 minute minimum
+        assert_eq!(next_chunk(50, 40, Some(30), None), None);
+        // 60 of 70 would strand 10, so book 40 and leave 30
+        assert_eq!(next_chunk(70, 240, Some(30), Some(60)), Some(40));
+    }
+
+    #[test]
+    fn next_chunk_books_small_tasks_whole() {
+        // A task shorter than its minimum chunk is one session
+        assert_eq!(next_chunk(20, 60, Some(45), None), Some(20));
+        assert_eq!(next_chunk(20, 10, Some(45), None), None);
+    }
+
+    #[test]
+    fn next_chunk_prefers_minimum_over_maximum() {
+        // 70 can't be split into sessions between 40 and 50 minutes
+        assert_eq!(next_chunk(70, 240, Some(40), Some(50)), Some(70));
+        assert_eq!(next_chunk(70, 60, Some(40), Some(50)), None);
+    }
+
+    #[test]
+    fn splits_evenly_and_absorbs_remainders() {
+        assert_eq!(split_into_chunks(90, Some(30)), vec![30, 30, 30]);
+        assert_eq!(split_into_chunks(100, Some(30)), vec![34, 33, 33]);
+        assert_eq!(split_into_chunks(20, Some(30)), vec![20]);
+        assert_eq!(split_into_chunks(90, None), vec![90]);
+        assert!(split_into_chunks(0, Some(30)).is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+use crate::prelude::*;
+
+use std::fmt;
+
+impl fmt::Display for TimeBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} - {}  {} ({})",
+            self.start.format("%Y-%m-%d %H:%M"),
+            self.end.format("%H:%M"),
+            self.task_name,
+            format_duration(self.minutes())
+        )
+    }
+}
+
+pub fn list_schedule(blocks: &[TimeBlock]) -> Result<()> {
+    if blocks.is_empty() {
+        println!("Nothing scheduled");
+    }
+
+    for block in blocks {
+        println!("{}", block);
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for AtRiskTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let finish = self
+            .projected_finish
+            .map(|finish| finish.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "beyond the planning horizon".to_string());
+        write!(
+            f,
+            "At risk: {} (due {}) - {} of work falls after the deadline, projected finish {}",
+            self.name,
+            self.due,
+            format_duration(self.minutes_late),
+            finish
+        )
+    }
+}
+
+pub fn list_at_risk_tasks(at_risk: &[AtRiskTask]) -> Result<()> {
+    for task in at_risk {
+        println!("{}", task);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use crate::prelude::*;
+use chrono::{Duration, NaiveDateTime};
+
+/// An open task whose remaining work cannot all be done before its due date.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtRiskTask {
+    pub task_id: Uuid,
+    pub name: String,
+    pub due: DueDate,
+    /// When the last minute of work lands, or `None` if it doesn't fit in the planning horizon
+    pub projected_finish: Option<NaiveDateTime>,
+    /// Minutes of work that fall after the deadline
+    pub minutes_late: u32,
+}
+
+/// The instant a task stops being on time: its due time, or the end of its
+/// due date when it has none.
+pub fn deadline(task: &Task) -> NaiveDateTime {
+    match task.due_time {
+        Some(time) => task.due_date.and_time(time),
+        None => (task.due_date + Duration::days(1))
+            .and_hms_opt(0, 0, 0)
+            .unwrap(),
+    }
+}
+
+/// Earliest-deadline-first deadline check, placed by the same allocator as
+/// `schedule`. EDF is only optimal for work that can be split anywhere; with
+/// minimum chunk sizes and dependencies it is a heuristic. A reported task
+/// may still fit under some other order, and one that passes here can be late
+/// in the priority-ordered schedule, so treat the result as a warning rather
+/// than a guarantee.
+pub fn check_feasibility(
+    tasks: &[Task],
+    availability: &Availability,
+    busy: &[BusyBlock],
+    from: NaiveDateTime,
+) -> Vec<AtRiskTask> {
+    // A parent is planned for its own remaining time; its subtasks are planned
+    // on their own
+    let mut open_tasks: Vec<Task> = tasks
+        .iter()
+        .filter(|t| is_schedulable(t))
+        .cloned()
+        .collect();
+    expand_parent_dependencies(&mut open_tasks, tasks);
+    open_tasks.sort_by(|a, b| {
+        deadline(a)
+            .cmp(&deadline(b))
+            .then_with(|| a.priority_level.cmp(&b.priority_level))
+    });
+    order_by_dependencies(&mut open_tasks);
+
+    let blocks = allocate(&open_tasks, availability, busy, from);
+
+    open_tasks
+        .iter()
+        .filter_map(|task| {
+            let deadline = deadline(task);
+            let task_blocks: Vec<&TimeBlock> = blocks
+                .iter()
+                .filter(|block| block.task_id == task.id)
+                .collect();
+
+            let placed: u32 = task_blocks.iter().map(|block| block.minutes()).sum();
+            let unplaced = task.time_remaining.saturating_sub(placed);
+            let after_deadline: u32 = task_blocks
+                .iter()
+                .filter(|block| block.end > deadline)
+                .map(|block| (block.end - block.start.max(deadline)).num_minutes() as u32)
+                .sum();
+
+            let minutes_late = after_deadline + unplaced;
+            if minutes_late == 0 {
+                return None;
+            }
+
+            Some(AtRiskTask {
+                task_id: task.id,
+                name: task.name.clone(),
+                due: task.due(),
+                projected_finish: if unplaced == 0 {
+                    task_blocks.iter().map(|block| block.end).max()
+                } else {
+                    None
+                },
+                minutes_late,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    fn monday() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 12, 2).unwrap()
+    }
+
+    #[test]
+    fn feasible_workload_has_no_warnings() {
+        let tasks = vec![
+            Task {
+                time_remaining: 120,
+                due_date: monday(),
+                ..sample_task("a")
+            },
+            Task {
+                time_remaining: 300,
+                due_date: monday() + Duration::days(1),
+                ..sample_task("b")
+            },
+        ];
+
+        let at_risk = check_feasibility(
+            &tasks,
+            &Availability::default(),
+            &[],
+            monday().and_hms_opt(9, 0, 0).unwrap(),
+        );
+
+        assert!(at_risk.is_empty());
+    }
+
+    #[test]
+    fn reports_how_late_an_overloaded_task_is() {
+        // A default day holds 7.5 hours, so the second task spills into Tuesday
+        let tasks = vec![
+            Task {
+                time_remaining: 300,
+                due_date: monday(),
+                ..sample_task("first")
+            },
+            Task {
+                time_remaining: 240,
+                due_date: monday(),
+                ..sample_task("second")
+            },
+        ];
+
+        let at_risk = check_feasibility(
+            &tasks,
+            &Availability::default(),
+            &[],
+            monday().and_hms_opt(9, 0, 0).unwrap(),
+        );
+
+        assert_eq!(at_risk.len(), 1);
+        assert_eq!(at_risk[0].name, "second");
+        assert_eq!(at_risk[0].minutes_late, 90);
+        assert_eq!(
+            at_risk[0].projected_finish,
+            Some(
+                (monday() + Duration::days(1))
+                    .and_hms_opt(10, 30, 0)
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn overdue_tasks_are_late_by_all_remaining_work() {
+        let tasks = vec![Task {
+            time_remaining: 45,
+            due_date: monday() - Duration::days(3),
+            ..sample_task("overdue")
+        }];
+
+        let at_risk = check_feasibility(
+            &tasks,
+            &Availability::default(),
+            &[],
+            monday().and_hms_opt(9, 0, 0).unwrap(),
+        );
+
+        assert_eq!(at_risk.len(), 1);
+        assert_eq!(at_risk[0].minutes_late, 45);
+    }
+
+    #[test]
+    fn due_times_move_the_deadline_into_the_day() {
+        let mut task = Task {
+            time_remaining: 120,
+            due_date: monday(),
+            ..sample_task("standup notes")
+        };
+        task.due_time = NaiveTime::from_hms_opt(10, 0, 0);
+
+        let at_risk = check_feasibility(
+            std::slice::from_ref(&task),
+            &Availability::default(),
+            &[],
+            monday().and_hms_opt(9, 0, 0).unwrap(),
+        );
+
+        assert_eq!(at_risk.len(), 1);
+        assert_eq!(at_risk[0].minutes_late, 60);
+        assert_eq!(at_risk[0].due.to_string(), "2024-12-02 10:00");
+    }
+}
@@ -0,0 +1,417 @@
+use crate::prelude::*;
+use chrono::{Duration, NaiveDateTime};
+use std::collections::{HashMap, HashSet};
+
+pub mod availability;
+pub mod chunk;
+pub mod display;
+pub mod feasibility;
+
+/// How far ahead of the starting instant the scheduler will look for free time.
+pub const PLANNING_HORIZON_DAYS: i64 = 365;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TimeBlock {
+    pub task_id: Uuid,
+    pub task_name: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl TimeBlock {
+    pub fn minutes(&self) -> u32 {
+        (self.end - self.start).num_minutes() as u32
+    }
+}
+
+pub fn is_schedulable(task: &Task) -> bool {
+    task.time_remaining > 0 && !matches!(task.status, Status::Completed | Status::Deleted)
+}
+
+/// Places every open task onto the calendar, earliest free time first, in the
+/// order produced by `schedule_tasks`, along with the occurrences of repeating
+/// tasks that fall due within the planning horizon. Only time inside
+/// `availability` that isn't `busy` is used.
+pub fn generate_schedule(
+    tasks: &[Task],
+    availability: &Availability,
+    busy: &[BusyBlock],
+    from: NaiveDateTime,
+) -> Vec<TimeBlock> {
+    // A parent is planned for its own remaining time; its subtasks are planned
+    // on their own
+    let mut open_tasks: Vec<Task> = tasks
+        .iter()
+        .filter(|t| is_schedulable(t))
+        .cloned()
+        .collect();
+    let horizon = from.date() + Duration::days(PLANNING_HORIZON_DAYS);
+    let upcoming: Vec<Task> = open_tasks
+        .iter()
+        .flat_map(|task| upcoming_occurrences(task, horizon))
+        .collect();
+    open_tasks.extend(upcoming);
+    expand_parent_dependencies(&mut open_tasks, tasks);
+    schedule_tasks(&mut open_tasks);
+
+    allocate(&open_tasks, availability, busy, from)
+}
+
+/// Fills free time with `tasks` strictly in the order given, so each task
+/// only gets what the ones ahead of it left over. Tasks are split into
+/// sessions that respect their chunk sizes, at most one per free window so a
+/// maximum chunk size actually breaks the work up; windows too short for a
+/// valid session are left for later tasks. A task never starts before the
+/// prerequisites listed ahead of it are finished, and isn't placed at all if
+/// one of them doesn't fit; an occurrence of a repeating task never starts
+/// before the previous one was due.
+pub fn allocate(
+    tasks: &[Task],
+    availability: &Availability,
+    busy: &[BusyBlock],
+    from: NaiveDateTime,
+) -> Vec<TimeBlock> {
+    let mut slots = availability.free_time(from, PLANNING_HORIZON_DAYS, busy);
+    let mut blocks = Vec::new();
+    let listed: HashSet<Uuid> = tasks.iter().map(|task| task.id).collect();
+    let mut finished: HashMap<Uuid, NaiveDateTime> = HashMap::new();
+
+    'tasks: for task in tasks {
+        let mut earliest = available_from(task).map_or(from, |start| from.max(start));
+        for id in task.depends_on.iter().filter(|id| listed.contains(id)) {
+            match finished.get(id) {
+                Some(end) => earliest = earliest.max(*end),
+                None => continue 'tasks,
+            }
+        }
+
+        let mut remaining = task.time_remaining;
+        let mut last_end = earliest;
+
+        for (slot_start, slot_end) in slots.iter_mut() {
+            if remaining == 0 {
+                break;
+            }
+
+            let start = (*slot_start).max(earliest);
+            let available = (*slot_end - start).num_minutes().max(0) as u32;
+            let Some(minutes) = next_chunk(
+                remaining,
+                available,
+                task.minimum_chunk_size,
+                task.maximum_chunk_size,
+            ) else {
+                continue;
+            };
+
+            let end = start + Duration::minutes(minutes as i64);
+            blocks.push(TimeBlock {
+                task_id: task.id,
+                task_name: task.name.clone(),
+                start,
+                end,
+            });
+
+            *slot_start = end;
+            last_end = end;
+            remaining -= minutes;
+        }
+
+        if remaining == 0 {
+            finished.insert(task.id, last_end);
+        }
+    }
+
+    blocks.sort_by_key(|block| block.start);
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    fn monday_morning() -> NaiveDateTime {
+        // 2024-12-02 is a Monday
+        NaiveDate::from_ymd_opt(2024, 12, 2)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn schedules_tasks_in_priority_order() {
+        let tasks = vec![
+            Task {
+                time_remaining: 60,
+                priority_level: Priority::Low,
+                ..sample_task("low")
+            },
+            Task {
+                time_remaining: 90,
+                priority_level: Priority::Urgent,
+                ..sample_task("urgent")
+            },
+        ];
+
+        let blocks = generate_schedule(&tasks, &Availability::default(), &[], monday_morning());
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].task_name, "urgent");
+        assert_eq!(blocks[0].start, monday_morning() + Duration::hours(1));
+        assert_eq!(blocks[0].minutes(), 90);
+        assert_eq!(blocks[1].task_name, "low");
+        assert_eq!(blocks[1].start, blocks[0].end);
+    }
+
+    #[test]
+    fn only_allocates_inside_working_windows() {
+        let tasks = vec![Task {
+            time_remaining: 240,
+            priority_level: Priority::High,
+            ..sample_task("long")
+        }];
+
+        let blocks = generate_schedule(&tasks, &Availability::default(), &[], monday_morning());
+
+        // 09:00-12:00 then the remaining hour after lunch
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].minutes(), 180);
+        assert_eq!(blocks[1].start, monday_morning() + Duration::hours(5));
+        assert_eq!(blocks[1].minutes(), 60);
+    }
+
+    #[test]
+    fn splits_work_across_days_and_skips_weekends() {
+        // Friday afternoon, 2024-12-06
+        let from = NaiveDate::from_ymd_opt(2024, 12, 6)
+            .unwrap()
+            .and_hms_opt(16, 30, 0)
+            .unwrap();
+        let tasks = vec![Task {
+            time_remaining: 120,
+            priority_level: Priority::High,
+            ..sample_task("long")
+        }];
+
+        let blocks = generate_schedule(&tasks, &Availability::default(), &[], from);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].minutes(), 60);
+        assert_eq!(
+            blocks[1].start,
+            NaiveDate::from_ymd_opt(2024, 12, 9)
+                .unwrap()
+                .and_hms_opt(9, 0, 0)
+                .unwrap()
+        );
+        assert_eq!(blocks[1].minutes(), 60);
+    }
+
+    #[test]
+    fn sessions_respect_chunk_sizes() {
+        let mut task = Task {
+            time_remaining: 150,
+            priority_level: Priority::High,
+            ..sample_task("chunked")
+        };
+        task.minimum_chunk_size = Some(45);
+        task.maximum_chunk_size = Some(60);
+        // Leave only half an hour before lunch, too short for a session
+        let from = monday_morning() + Duration::minutes(210);
+
+        let blocks = generate_schedule(&[task], &Availability::default(), &[], from);
+
+        let minutes: Vec<u32> = blocks.iter().map(TimeBlock::minutes).collect();
+        assert_eq!(minutes, vec![60, 45, 45]);
+        assert_eq!(blocks[0].start, monday_morning() + Duration::hours(5));
+        assert_eq!(blocks[1].start, monday_morning() + Duration::hours(25));
+        assert_eq!(blocks[2].start, monday_morning() + Duration::hours(29));
+    }
+
+    #[test]
+    fn skips_completed_and_empty_tasks() {
+        let mut done = Task {
+            time_remaining: 60,
+            priority_level: Priority::Urgent,
+            ..sample_task("done")
+        };
+        done.status = Status::Completed;
+        let empty = Task {
+            time_remaining: 0,
+            priority_level: Priority::Urgent,
+            ..sample_task("empty")
+        };
+
+        let blocks = generate_schedule(
+            &[done, empty],
+            &Availability::default(),
+            &[],
+            monday_morning(),
+        );
+
+        assert!(blocks.is_empty());
+    }
+
+    #[test]
+    fn dependents_start_after_their_prerequisites_finish() {
+        let write = Task {
+            time_remaining: 120,
+            priority_level: Priority::Low,
+            ..sample_task("write")
+        };
+        let mut review = Task {
+            time_remaining: 60,
+            priority_level: Priority::Urgent,
+            ..sample_task("review")
+        };
+        review.depends_on = vec![write.id];
+        let other = Task {
+            time_remaining: 30,
+            priority_level: Priority::Medium,
+            ..sample_task("other")
+        };
+
+        let blocks = generate_schedule(
+            &[review, other, write],
+            &Availability::default(),
+            &[],
+            monday_morning(),
+        );
+
+        let names: Vec<&str> = blocks.iter().map(|b| b.task_name.as_str()).collect();
+        assert_eq!(names, vec!["write", "review", "other"]);
+        assert_eq!(blocks[1].start, blocks[0].end);
+    }
+
+    #[test]
+    fn dependents_wait_for_prerequisites_that_do_not_fit() {
+        // No working window is ten hours long
+        let mut first = Task {
+            time_remaining: 600,
+            priority_level: Priority::High,
+            ..sample_task("first")
+        };
+        first.minimum_chunk_size = Some(600);
+        let mut second = Task {
+            time_remaining: 60,
+            priority_level: Priority::High,
+            ..sample_task("second")
+        };
+        second.depends_on = vec![first.id];
+
+        let blocks = allocate(
+            &[first, second],
+            &Availability::default(),
+            &[],
+            monday_morning(),
+        );
+
+        assert!(blocks.is_empty());
+    }
+
+    #[test]
+    fn schedules_subtasks_instead_of_their_parent() {
+        let project = Task {
+            time_remaining: 0,
+            priority_level: Priority::High,
+            ..sample_task("project")
+        };
+        let mut draft = Task {
+            time_remaining: 60,
+            priority_level: Priority::High,
+            ..sample_task("draft")
+        };
+        draft.parent = Some(project.id);
+        let mut publish = Task {
+            time_remaining: 30,
+            priority_level: Priority::Urgent,
+            ..sample_task("publish")
+        };
+        publish.depends_on = vec![project.id];
+
+        let blocks = generate_schedule(
+            &[publish, project, draft],
+            &Availability::default(),
+            &[],
+            monday_morning(),
+        );
+
+        let names: Vec<&str> = blocks.iter().map(|b| b.task_name.as_str()).collect();
+        assert_eq!(names, vec!["draft", "publish"]);
+    }
+
+    #[test]
+    fn schedules_the_own_time_of_parents() {
+        let project = Task {
+            time_remaining: 60,
+            priority_level: Priority::High,
+            ..sample_task("project")
+        };
+        let mut draft = Task {
+            time_remaining: 60,
+            priority_level: Priority::High,
+            ..sample_task("draft")
+        };
+        draft.parent = Some(project.id);
+        let dropped_project = Task {
+            time_remaining: 30,
+            priority_level: Priority::High,
+            ..sample_task("dropped project")
+        };
+        let mut dropped = Task {
+            time_remaining: 60,
+            priority_level: Priority::High,
+            ..sample_task("dropped")
+        };
+        dropped.parent = Some(dropped_project.id);
+        dropped.status = Status::Deleted;
+        let mut publish = Task {
+            time_remaining: 30,
+            priority_level: Priority::Urgent,
+            ..sample_task("publish")
+        };
+        publish.depends_on = vec![project.id];
+
+        let blocks = generate_schedule(
+            &[publish, project, draft, dropped_project, dropped],
+            &Availability::default(),
+            &[],
+            monday_morning(),
+        );
+
+        let names: Vec<&str> = blocks.iter().map(|b| b.task_name.as_str()).collect();
+        assert!(names.contains(&"project"));
+        assert!(names.contains(&"dropped project"));
+        assert!(!names.contains(&"dropped"));
+        let publish = names.iter().position(|name| *name == "publish").unwrap();
+        assert!(names[..publish].contains(&"project"));
+        assert!(names[..publish].contains(&"draft"));
+    }
+
+    #[test]
+    fn plans_upcoming_occurrences_after_the_previous_one_is_due() {
+        let mut review = Task {
+            time_remaining: 30,
+            priority_level: Priority::High,
+            ..sample_task("review")
+        };
+        // Due Tuesday, then every day; each day's review waits for that day
+        review.due_date = NaiveDate::from_ymd_opt(2024, 12, 3).unwrap();
+        review.recurrence = Some("daily".parse().unwrap());
+        review.recurrence.as_mut().unwrap().count = Some(3);
+
+        let blocks = generate_schedule(&[review], &Availability::default(), &[], monday_morning());
+
+        let starts: Vec<NaiveDateTime> = blocks.iter().map(|block| block.start).collect();
+        assert_eq!(
+            starts,
+            vec![
+                monday_morning() + Duration::hours(25),
+                monday_morning() + Duration::hours(49),
+                monday_morning() + Duration::hours(73),
+            ]
+        );
+        assert_ne!(blocks[0].task_id, blocks[1].task_id);
+    }
+}
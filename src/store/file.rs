@@ -0,0 +1,198 @@
+use crate::prelude::*;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileFormat {
+    Yaml,
+    Json,
+}
+
+/// The whole task list in one YAML or JSON file, written atomically with
+/// rotating backups. The file is backed up before the first write through a
+/// store only, so one command takes up one backup however often it writes.
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    path: PathBuf,
+    format: FileFormat,
+    backed_up: bool,
+}
+
+impl FileStore {
+    pub fn yaml(path: &Path) -> Self {
+        FileStore {
+            path: path.to_path_buf(),
+            format: FileFormat::Yaml,
+            backed_up: false,
+        }
+    }
+
+    pub fn json(path: &Path) -> Self {
+        FileStore {
+            path: path.to_path_buf(),
+            format: FileFormat::Json,
+            backed_up: false,
+        }
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<Task>> {
+        match self.format {
+            FileFormat::Yaml => read_tasks(path),
+            FileFormat::Json => read_tasks_json(path),
+        }
+    }
+
+    fn write(&mut self, tasks: &[Task]) -> Result<()> {
+        if !self.backed_up {
+            rotate_backups(&self.path, BACKUP_COUNT)?;
+            self.backed_up = true;
+        }
+        match self.format {
+            FileFormat::Yaml => write_tasks_to_yaml(tasks, &self.path),
+            FileFormat::Json => write_tasks_to_json(tasks, &self.path),
+        }
+    }
+
+    /// Replaces the task file with backup `index`. The current file is backed
+    /// up first, so a restore can itself be rolled back.
+    pub fn restore(&mut self, index: usize) -> Result<Vec<Task>> {
+        let path = backup_path(&self.path, index);
+        if !path.exists() {
+            return Err(Error::Generic(format!(
+                "No backup {} for {}",
+                index,
+                self.path.display()
+            )));
+        }
+
+        // Make sure the backup is readable before it replaces anything
+        let tasks = self.read(&path)?;
+        self.write(&tasks)?;
+
+        Ok(tasks)
+    }
+}
+
+impl TaskStore for FileStore {
+    fn load(&self) -> Result<Vec<Task>> {
+        self.read(&self.path)
+    }
+
+    fn get(&self, id: &Uuid) -> Result<Option<Task>> {
+        Ok(self.load()?.into_iter().find(|task| task.id == *id))
+    }
+
+    fn insert(&mut self, task: Task) -> Result<()> {
+        let mut tasks = self.load()?;
+        if tasks.iter().any(|existing| existing.id == task.id) {
+            return Err(Error::Generic(format!("Task {} already exists", task.id)));
+        }
+
+        tasks.push(task);
+        self.write(&tasks)
+    }
+
+    fn update(&mut self, task: Task) -> Result<()> {
+        let mut tasks = self.load()?;
+        update_task_in_list(&mut tasks, task)?;
+        self.write(&tasks)
+    }
+
+    fn delete(&mut self, id: &Uuid) -> Result<()> {
+        let mut tasks = self.load()?;
+        let count = tasks.len();
+        tasks.retain(|task| task.id != *id);
+        if tasks.len() == count {
+            return Err(Error::Generic("Task not found in list".to_string()));
+        }
+
+        self.write(&tasks)
+    }
+
+    fn query(&self, query: &TaskQuery) -> Result<Vec<Task>> {
+        Ok(self
+            .load()?
+            .into_iter()
+            .filter(|task| query.matches(task))
+            .collect())
+    }
+
+    fn save_all(&mut self, tasks: Vec<Task>) -> Result<()> {
+        self.write(&tasks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::test_support::exercise_store;
+    use crate::test_support::sample_task;
+    use std::fs;
+
+    #[test]
+    fn yaml_store_behaves_like_a_store() {
+        let temp_dir = PathBuf::from("test_yaml_store_dir");
+        fs::create_dir_all(&temp_dir).expect("Failed to create test directory");
+
+        exercise_store(&mut FileStore::yaml(&temp_dir.join("tasks.yaml")));
+
+        fs::remove_dir_all(&temp_dir).expect("Failed to delete test directory");
+    }
+
+    #[test]
+    fn json_store_behaves_like_a_store() {
+        let temp_dir = PathBuf::from("test_json_store_dir");
+        fs::create_dir_all(&temp_dir).expect("Failed to create test directory");
+        let temp_file = temp_dir.join("tasks.json");
+
+        exercise_store(&mut FileStore::json(&temp_file));
+        let written = fs::read_to_string(&temp_file).expect("Failed to read output file");
+
+        fs::remove_dir_all(&temp_dir).expect("Failed to delete test directory");
+        assert!(written.contains(&format!("\"version\": {}", SCHEMA_VERSION)));
+    }
+
+    #[test]
+    fn restores_a_previous_version() {
+        let temp_dir = PathBuf::from("test_restore_dir");
+        fs::create_dir_all(&temp_dir).expect("Failed to create test directory");
+        let temp_file = temp_dir.join("tasks.yaml");
+        // A store per command, as the CLI opens them
+        FileStore::yaml(&temp_file)
+            .insert(sample_task("kept"))
+            .expect("Failed to insert");
+        FileStore::yaml(&temp_file)
+            .save_all(Vec::new())
+            .expect("Failed to save");
+
+        let mut store = FileStore::yaml(&temp_file);
+        let restored = store.restore(1).expect("Failed to restore");
+        let current = store.load().expect("Failed to read tasks");
+        let missing = store.restore(BACKUP_COUNT);
+
+        fs::remove_dir_all(&temp_dir).expect("Failed to delete test directory");
+        assert_eq!(restored.len(), 1);
+        assert_eq!(current[0].name, "kept");
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn backs_up_once_per_store() {
+        let temp_dir = PathBuf::from("test_backup_once_dir");
+        fs::create_dir_all(&temp_dir).expect("Failed to create test directory");
+        let temp_file = temp_dir.join("tasks.yaml");
+        FileStore::yaml(&temp_file)
+            .insert(sample_task("before"))
+            .expect("Failed to insert");
+
+        let mut store = FileStore::yaml(&temp_file);
+        for name in ["first", "second", "third"] {
+            store.insert(sample_task(name)).expect("Failed to insert");
+        }
+        let backups = list_backups(&temp_file).expect("Failed to list backups");
+        let latest = read_tasks(backup_path(&temp_file, 1)).expect("Failed to read backup");
+
+        fs::remove_dir_all(&temp_dir).expect("Failed to delete test directory");
+        assert_eq!(backups.len(), 1);
+        assert_eq!(latest.len(), 1);
+    }
+}
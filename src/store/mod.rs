@@ -0,0 +1,193 @@
+use crate::prelude::*;
+use std::path::Path;
+
+pub mod file;
+pub mod sqlite;
+
+pub use file::FileStore;
+pub use sqlite::SqliteStore;
+
+/// Where tasks are persisted. File stores rewrite the whole file on every
+/// change; SQLite updates single rows, which keeps long histories cheap.
+pub trait TaskStore {
+    fn load(&self) -> Result<Vec<Task>>;
+    fn get(&self, id: &Uuid) -> Result<Option<Task>>;
+    fn insert(&mut self, task: Task) -> Result<()>;
+    fn update(&mut self, task: Task) -> Result<()>;
+    fn delete(&mut self, id: &Uuid) -> Result<()>;
+    fn query(&self, query: &TaskQuery) -> Result<Vec<Task>>;
+    /// Replaces every stored task, for bulk changes like imports and restores.
+    fn save_all(&mut self, tasks: Vec<Task>) -> Result<()>;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Yaml,
+    Json,
+    Sqlite,
+}
+
+impl StorageBackend {
+    pub const ALL: [StorageBackend; 3] = [
+        StorageBackend::Yaml,
+        StorageBackend::Json,
+        StorageBackend::Sqlite,
+    ];
+
+    /// Extension of the default task file for this backend.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            StorageBackend::Yaml => "yaml",
+            StorageBackend::Json => "json",
+            StorageBackend::Sqlite => "db",
+        }
+    }
+}
+
+/// Filters for `TaskStore::query`; the default matches every task.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskQuery {
+    pub id_prefix: Option<String>,
+    pub exclude_statuses: Vec<Status>,
+    /// Tasks must have every one of these tags, normalized with `normalize_tag`
+    pub tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    /// Tasks must be in one of these projects, if any are given
+    pub projects: Vec<String>,
+    pub exclude_projects: Vec<String>,
+}
+
+impl TaskQuery {
+    pub fn id_prefix(prefix: &str) -> Self {
+        TaskQuery {
+            id_prefix: Some(prefix.to_string()),
+            ..TaskQuery::default()
+        }
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        self.id_prefix
+            .as_ref()
+            .is_none_or(|prefix| task.id.to_string().starts_with(prefix.as_str()))
+            && !self.exclude_statuses.contains(&task.status)
+            && self.tags.iter().all(|tag| task.tags.contains(tag))
+            && !self.exclude_tags.iter().any(|tag| task.tags.contains(tag))
+            && (self.projects.is_empty()
+                || self.projects.iter().any(|project| task.in_project(project)))
+            && !self
+                .exclude_projects
+                .iter()
+                .any(|project| task.in_project(project))
+    }
+}
+
+pub fn open_store(backend: StorageBackend, path: &Path) -> Result<Box<dyn TaskStore>> {
+    Ok(match backend {
+        StorageBackend::Yaml => Box::new(FileStore::yaml(path)),
+        StorageBackend::Json => Box::new(FileStore::json(path)),
+        StorageBackend::Sqlite => Box::new(SqliteStore::open(path)?),
+    })
+}
+
+/// Opens the task file with the backend chosen in the config file.
+pub fn open_task_store(paths: &DataPaths) -> Result<Box<dyn TaskStore>> {
+    let config = read_config(&paths.config)?;
+    open_store(config.storage.backend, &paths.tasks)
+}
+
+/// The task whose id starts with `prefix`, if exactly one does.
+pub fn find_single_task(store: &dyn TaskStore, prefix: &str) -> Result<Option<Task>> {
+    let mut matching = store.query(&TaskQuery::id_prefix(prefix))?;
+    if matching.len() == 1 {
+        Ok(matching.pop())
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    /// Runs the same behaviour checks against any backend.
+    pub fn exercise_store(store: &mut dyn TaskStore) {
+        let mut first = sample_task("first");
+        first.tags = vec!["work".to_string()];
+        first.project = Some("Website".to_string());
+        let mut second = sample_task("second");
+        second.status = Status::Completed;
+
+        store.insert(first.clone()).expect("Failed to insert");
+        store.insert(second.clone()).expect("Failed to insert");
+        assert!(store.insert(first.clone()).is_err());
+
+        let mut renamed = first.clone();
+        renamed.name = "renamed".to_string();
+        store.update(renamed).expect("Failed to update");
+        assert_eq!(store.get(&first.id).unwrap().unwrap().name, "renamed");
+
+        let open = store
+            .query(&TaskQuery {
+                exclude_statuses: vec![Status::Completed],
+                ..TaskQuery::default()
+            })
+            .unwrap();
+        assert_eq!(open.len(), 1);
+        let prefix = &second.id.to_string()[..8];
+        assert_eq!(
+            store.query(&TaskQuery::id_prefix(prefix)).unwrap()[0].id,
+            second.id
+        );
+        let names = |query: TaskQuery| -> Vec<String> {
+            store
+                .query(&query)
+                .unwrap()
+                .into_iter()
+                .map(|task| task.name)
+                .collect()
+        };
+        assert_eq!(
+            names(TaskQuery {
+                tags: vec!["work".to_string()],
+                ..TaskQuery::default()
+            }),
+            vec!["renamed"]
+        );
+        assert_eq!(
+            names(TaskQuery {
+                exclude_tags: vec!["work".to_string()],
+                ..TaskQuery::default()
+            }),
+            vec!["second"]
+        );
+        assert_eq!(
+            names(TaskQuery {
+                projects: vec!["website".to_string(), "other".to_string()],
+                ..TaskQuery::default()
+            }),
+            vec!["renamed"]
+        );
+        assert_eq!(
+            names(TaskQuery {
+                exclude_projects: vec!["WEBSITE".to_string()],
+                ..TaskQuery::default()
+            }),
+            vec!["second"]
+        );
+
+        store.delete(&second.id).expect("Failed to delete");
+        assert!(store.get(&second.id).unwrap().is_none());
+        assert!(store.delete(&second.id).is_err());
+        assert!(store.update(second).is_err());
+
+        store
+            .save_all(vec![sample_task("only")])
+            .expect("Failed to save");
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "only");
+    }
+}
@@ -0,0 +1,266 @@
+use crate::prelude::*;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::path::Path;
+
+/// Tasks in an SQLite database, one row per task. The full task is kept as
+/// JSON next to the columns queries filter on, so adding fields to `Task`
+/// doesn't need a table change.
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        SqliteStore::from_connection(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn in_memory() -> Result<Self> {
+        SqliteStore::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(connection: Connection) -> Result<Self> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS tasks (
+                id TEXT PRIMARY KEY,
+                status TEXT NOT NULL,
+                data TEXT NOT NULL
+            )",
+        )?;
+        let mut store = SqliteStore { connection };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    /// The schema version lives in SQLite's `user_version`. Rows written by
+    /// an older auto-cal are upgraded in one transaction when the database is
+    /// opened.
+    fn migrate(&mut self) -> Result<()> {
+        let version: u32 = self
+            .connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version == SCHEMA_VERSION {
+            return Ok(());
+        }
+        check_supported(version)?;
+
+        let transaction = self.connection.transaction()?;
+        let rows = {
+            let mut statement = transaction.prepare("SELECT id, data FROM tasks")?;
+            let rows = statement.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        for (id, data) in rows {
+            let value = serde_json::from_str(&data).map_err(|e| {
+                Error::Generic(format!("Failed to deserialize task {}. Error: {}", id, e))
+            })?;
+            let task = migrate_task(value, version)?;
+            transaction.execute(
+                "UPDATE tasks SET data = ?2 WHERE id = ?1",
+                params![id, to_json(&task)?],
+            )?;
+        }
+        transaction.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn select(&self, filter: &str, values: Vec<String>) -> Result<Vec<Task>> {
+        let sql = format!("SELECT data FROM tasks {} ORDER BY rowid", filter);
+        let mut statement = self.connection.prepare(&sql)?;
+        let rows = statement.query_map(params_from_iter(values), |row| row.get::<_, String>(0))?;
+
+        rows.map(|data| from_json(&data?)).collect()
+    }
+}
+
+fn to_json(task: &Task) -> Result<String> {
+    serde_json::to_string(task)
+        .map_err(|e| Error::Generic(format!("Failed to serialize task to JSON. Error: {}", e)))
+}
+
+fn from_json(data: &str) -> Result<Task> {
+    serde_json::from_str(data).map_err(|e| {
+        Error::Generic(format!(
+            "Failed to deserialize task from JSON. Error: {}",
+            e
+        ))
+    })
+}
+
+impl TaskStore for SqliteStore {
+    fn load(&self) -> Result<Vec<Task>> {
+        self.select("", Vec::new())
+    }
+
+    fn get(&self, id: &Uuid) -> Result<Option<Task>> {
+        self.connection
+            .query_row(
+                "SELECT data FROM tasks WHERE id = ?1",
+                params![id.to_string()],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .map(|data| from_json(&data))
+            .transpose()
+    }
+
+    fn insert(&mut self, task: Task) -> Result<()> {
+        if self.get(&task.id)?.is_some() {
+            return Err(Error::Generic(format!("Task {} already exists", task.id)));
+        }
+
+        self.connection.execute(
+            "INSERT INTO tasks (id, status, data) VALUES (?1, ?2, ?3)",
+            params![
+                task.id.to_string(),
+                task.status.to_string(),
+                to_json(&task)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn update(&mut self, task: Task) -> Result<()> {
+        let changed = self.connection.execute(
+            "UPDATE tasks SET status = ?2, data = ?3 WHERE id = ?1",
+            params![
+                task.id.to_string(),
+                task.status.to_string(),
+                to_json(&task)?
+            ],
+        )?;
+        if changed == 0 {
+            return Err(Error::Generic("Task not found in list".to_string()));
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, id: &Uuid) -> Result<()> {
+        let changed = self
+            .connection
+            .execute("DELETE FROM tasks WHERE id = ?1", params![id.to_string()])?;
+        if changed == 0 {
+            return Err(Error::Generic("Task not found in list".to_string()));
+        }
+        Ok(())
+    }
+
+    fn query(&self, query: &TaskQuery) -> Result<Vec<Task>> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+
+        if let Some(prefix) = &query.id_prefix {
+            conditions.push("substr(id, 1, length(?)) = ?".to_string());
+            values.push(prefix.clone());
+            values.push(prefix.clone());
+        }
+        for status in &query.exclude_statuses {
+            conditions.push("status != ?".to_string());
+            values.push(status.to_string());
+        }
+        for tag in &query.tags {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM json_each(data, '$.tags') WHERE value = ?)".to_string(),
+            );
+            values.push(tag.clone());
+        }
+        for tag in &query.exclude_tags {
+            conditions.push(
+                "NOT EXISTS (SELECT 1 FROM json_each(data, '$.tags') WHERE value = ?)".to_string(),
+            );
+            values.push(tag.clone());
+        }
+        // lower() only folds ASCII, the same as `Task::in_project`
+        if !query.projects.is_empty() {
+            let placeholders = vec!["lower(?)"; query.projects.len()].join(", ");
+            conditions.push(format!(
+                "lower(json_extract(data, '$.project')) IN ({})",
+                placeholders
+            ));
+            values.extend(query.projects.iter().cloned());
+        }
+        for project in &query.exclude_projects {
+            conditions.push(
+                "coalesce(lower(json_extract(data, '$.project')) != lower(?), 1)".to_string(),
+            );
+            values.push(project.clone());
+        }
+
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        self.select(&filter, values)
+    }
+
+    fn save_all(&mut self, tasks: Vec<Task>) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute("DELETE FROM tasks", [])?;
+        for task in &tasks {
+            transaction.execute(
+                "INSERT INTO tasks (id, status, data) VALUES (?1, ?2, ?3)",
+                params![task.id.to_string(), task.status.to_string(), to_json(task)?],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::test_support::exercise_store;
+
+    #[test]
+    fn sqlite_store_behaves_like_a_store() {
+        exercise_store(&mut SqliteStore::in_memory().expect("Failed to open database"));
+    }
+
+    #[test]
+    fn upgrades_rows_from_older_versions() {
+        let connection = Connection::open_in_memory().unwrap();
+        let task: serde_json::Value = serde_yaml::from_str::<Vec<serde_json::Value>>(include_str!(
+            "../../tests/fixtures/tasks_v1.yaml"
+        ))
+        .unwrap()
+        .remove(0);
+        connection
+            .execute_batch(
+                "CREATE TABLE tasks (id TEXT PRIMARY KEY, status TEXT NOT NULL, data TEXT NOT NULL);
+                 PRAGMA user_version = 1;",
+            )
+            .unwrap();
+        connection
+            .execute(
+                "INSERT INTO tasks (id, status, data) VALUES (?1, ?2, ?3)",
+                params![task["id"].as_str(), "OnHold", task.to_string()],
+            )
+            .unwrap();
+
+        let store = SqliteStore::from_connection(connection).expect("Failed to migrate");
+        let version: u32 = store
+            .connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        let tasks = store.load().unwrap();
+
+        assert_eq!(version, SCHEMA_VERSION);
+        assert_eq!(tasks[0].elapsed_time, 30);
+        assert!(tasks[0].status_history.is_empty());
+    }
+
+    #[test]
+    fn rejects_databases_from_a_newer_version() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+
+        assert!(SqliteStore::from_connection(connection).is_err());
+    }
+}
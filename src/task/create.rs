@@ -1,52 +1,37 @@
 use crate::prelude::*;
 
-pub fn add_task(
-    name: String,
-    time_remaining: u32,
-    due_date: NaiveDate,
-    priority_level: Priority,
-    minimum_chunk_size: Option<u32>,
-    file_path: &str,
-) -> Result<()> {
-    let new_task = create_task(
-        name,
-        time_remaining,
-        due_date,
-        priority_level,
-        minimum_chunk_size,
-    )?;
-
-    let mut tasks = read_tasks(file_path)?;
-
-    // Append the new task
-    tasks.push(new_task.clone());
-
-    schedule_tasks(&mut tasks);
-
-    write_tasks_to_yaml(&mut tasks, file_path);
-
-    Ok(())
-}
-
 pub fn create_task(
     name: String,
     time_remaining: u32,
-    due_date: NaiveDate,
+    due_date: impl Into<DueDate>,
     priority_level: Priority,
     minimum_chunk_size: Option<u32>,
+    maximum_chunk_size: Option<u32>,
 ) -> Result<Task> {
     let current_date_time = Utc::now();
+    let due = due_date.into();
     let task = Task {
         id: Uuid::new_v4(),
         name,
         time_remaining,
-        due_date,
+        due_date: due.date,
+        due_time: due.time,
+        depends_on: Vec::new(),
+        parent: None,
+        recurrence: None,
+        tags: Vec::new(),
+        project: None,
+        description: None,
+        links: Vec::new(),
+        annotations: Vec::new(),
         status: Status::UnStarted,
         created_date: current_date_time,
         priority_level,
         minimum_chunk_size,
+        maximum_chunk_size,
         elapsed_time: 0,
         work_intervals: Vec::new(),
+        status_history: Vec::new(),
     };
 
     Ok(task)
@@ -63,6 +48,7 @@ mod tests {
         let due_date = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
         let priority_level = Priority::High;
         let minimum_chunk_size = None;
+        let maximum_chunk_size = None;
 
         let task = create_task(
             task_name.clone(),
@@ -70,6 +56,7 @@ mod tests {
             due_date,
             priority_level,
             minimum_chunk_size,
+            maximum_chunk_size,
         )
         .unwrap();
 
@@ -0,0 +1,175 @@
+use crate::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Looks up the tasks named by id `prefixes`, each of which must match exactly
+/// one task.
+pub fn resolve_dependencies(prefixes: &[String], tasks: &[Task]) -> Result<Vec<Uuid>> {
+    prefixes
+        .iter()
+        .map(|prefix| {
+            get_task(tasks, prefix).map(|task| task.id).ok_or_else(|| {
+                Error::Generic(format!("No single task matches dependency {}", prefix))
+            })
+        })
+        .collect()
+}
+
+/// Prerequisites of `task` that still need doing, i.e. anything not completed
+/// or deleted. Dependencies on tasks that no longer exist are ignored.
+pub fn open_dependencies<'a>(task: &Task, tasks: &'a [Task]) -> Vec<&'a Task> {
+    tasks
+        .iter()
+        .filter(|other| task.depends_on.contains(&other.id))
+        .filter(|other| !matches!(other.status, Status::Completed | Status::Deleted))
+        .collect()
+}
+
+/// Fails if following `depends_on` from any task leads back to that task.
+pub fn check_dependencies(tasks: &[Task]) -> Result<()> {
+    let by_id: HashMap<Uuid, &Task> = tasks.iter().map(|task| (task.id, task)).collect();
+    let mut finished = HashSet::new();
+
+    for task in tasks {
+        if let Some(cycle) = find_cycle(task, &by_id, &mut Vec::new(), &mut finished) {
+            let labels: Vec<String> = cycle.iter().map(|task| task.label()).collect();
+            return Err(Error::DependencyCycle(labels.join(" -> ")));
+        }
+    }
+    Ok(())
+}
+
+fn find_cycle<'a>(
+    task: &'a Task,
+    by_id: &HashMap<Uuid, &'a Task>,
+    path: &mut Vec<&'a Task>,
+    finished: &mut HashSet<Uuid>,
+) -> Option<Vec<&'a Task>> {
+    if finished.contains(&task.id) {
+        return None;
+    }
+    if let Some(start) = path.iter().position(|other| other.id == task.id) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(task);
+        return Some(cycle);
+    }
+
+    path.push(task);
+    for id in &task.depends_on {
+        if let Some(dependency) = by_id.get(id) {
+            if let Some(cycle) = find_cycle(dependency, by_id, path, finished) {
+                return Some(cycle);
+            }
+        }
+    }
+    path.pop();
+    finished.insert(task.id);
+    None
+}
+
+/// Reorders `tasks` so each one comes after the prerequisites that are also in
+/// the list. Otherwise the existing order is kept, except that prerequisites
+/// are pulled forward to just ahead of the first task waiting on them.
+pub fn order_by_dependencies(tasks: &mut [Task]) {
+    let index: HashMap<Uuid, usize> = tasks
+        .iter()
+        .enumerate()
+        .map(|(i, task)| (task.id, i))
+        .collect();
+    let mut visited = vec![false; tasks.len()];
+    let mut order = Vec::with_capacity(tasks.len());
+
+    for i in 0..tasks.len() {
+        place(i, tasks, &index, &mut visited, &mut order);
+    }
+
+    let ordered: Vec<Task> = order.into_iter().map(|i| tasks[i].clone()).collect();
+    tasks.clone_from_slice(&ordered);
+}
+
+fn place(
+    i: usize,
+    tasks: &[Task],
+    index: &HashMap<Uuid, usize>,
+    visited: &mut [bool],
+    order: &mut Vec<usize>,
+) {
+    // Marking before recursing also stops at cycles
+    if visited[i] {
+        return;
+    }
+    visited[i] = true;
+
+    let mut dependencies: Vec<usize> = tasks[i]
+        .depends_on
+        .iter()
+        .filter_map(|id| index.get(id).copied())
+        .collect();
+    dependencies.sort();
+    for j in dependencies {
+        place(j, tasks, index, visited, order);
+    }
+    order.push(i);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    fn names(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|task| task.name.as_str()).collect()
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let mut write = sample_task("write");
+        let mut review = sample_task("review");
+        let publish = sample_task("publish");
+        review.depends_on = vec![write.id];
+        write.depends_on = vec![publish.id];
+        let tasks = vec![write.clone(), review.clone(), publish.clone()];
+        assert!(check_dependencies(&tasks).is_ok());
+
+        write.depends_on.push(review.id);
+        let error = check_dependencies(&[write, review, publish]).unwrap_err();
+        assert!(matches!(&error, Error::DependencyCycle(path) if path.starts_with("write")));
+        assert!(error.to_string().contains("review"));
+    }
+
+    #[test]
+    fn self_dependencies_are_cycles() {
+        let mut task = sample_task("loop");
+        task.depends_on = vec![task.id];
+
+        assert!(check_dependencies(&[task]).is_err());
+    }
+
+    #[test]
+    fn prerequisites_move_ahead_of_dependents() {
+        let write = sample_task("write");
+        let other = sample_task("other");
+        let mut review = sample_task("review");
+        review.depends_on = vec![write.id];
+        let mut tasks = vec![review, other, write];
+
+        order_by_dependencies(&mut tasks);
+
+        assert_eq!(names(&tasks), vec!["write", "review", "other"]);
+    }
+
+    #[test]
+    fn only_unfinished_prerequisites_block() {
+        let mut done = sample_task("done");
+        done.status = Status::Completed;
+        let open = sample_task("open");
+        let mut task = sample_task("task");
+        task.depends_on = vec![done.id, open.id, Uuid::new_v4()];
+        let tasks = vec![done, open, task.clone()];
+
+        let blocking: Vec<&str> = open_dependencies(&task, &tasks)
+            .iter()
+            .map(|task| task.name.as_str())
+            .collect();
+        assert_eq!(blocking, vec!["open"]);
+    }
+}
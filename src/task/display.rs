@@ -6,9 +6,35 @@ impl fmt::Display for Task {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "ID: {}\nTask: {}\nTime Remaining: {} minutes\nDue Date: {}\nStatus: {}\nPriority: {}\nChunks Remaining: {}",
-            self.id, self.name, self.time_remaining, self.due_date, self.status, self.priority_level, chunks_remaining(self)
-        )
+            "ID: {}\nTask: {}\nTime Remaining: {}\nDue Date: {}\nStatus: {}\nPriority: {}\nChunks Remaining: {}",
+            self.id, self.name, format_duration(self.time_remaining), self.due(), self.status, self.priority_level, chunks_remaining(self)
+        )?;
+        if !self.depends_on.is_empty() {
+            let ids: Vec<String> = self
+                .depends_on
+                .iter()
+                .map(|id| id.to_string()[..8].to_string())
+                .collect();
+            write!(f, "\nDepends On: {}", ids.join(", "))?;
+        }
+        if let Some(project) = &self.project {
+            write!(f, "\nProject: {}", project)?;
+        }
+        if !self.tags.is_empty() {
+            let tags: Vec<String> = self.tags.iter().map(|tag| format!("#{}", tag)).collect();
+            write!(f, "\nTags: {}", tags.join(" "))?;
+        }
+        if let Some(recurrence) = &self.recurrence {
+            write!(f, "\nRepeats: {}", recurrence)?;
+        }
+        Ok(())
+    }
+}
+
+impl Task {
+    /// Name and short id, enough to tell tasks apart in messages.
+    pub fn label(&self) -> String {
+        format!("{} ({})", self.name, &self.id.to_string()[..8])
     }
 }
 
@@ -24,6 +50,89 @@ impl fmt::Display for Priority {
     }
 }
 
+/// Everything about `task` for `show`, one field per line, with timestamps in
+/// `zone`. Fields that are empty are left out; `tasks` are used to name its
+/// parent, subtasks and prerequisites.
+pub fn task_details(task: &Task, tasks: &[Task], zone: Zone) -> String {
+    let labels = |ids: &mut dyn Iterator<Item = Uuid>| -> Vec<String> {
+        ids.map(|id| match tasks.iter().find(|other| other.id == id) {
+            Some(other) => format!("{} [{}]", other.label(), other.status),
+            None => format!("{} (missing)", &id.to_string()[..8]),
+        })
+        .collect()
+    };
+    let local = |at: &DateTime<Utc>| zone.local(*at).format("%Y-%m-%d %H:%M").to_string();
+
+    let mut lines = vec![
+        format!("ID: {}", task.id),
+        format!("Task: {}", task.name),
+        format!("Status: {}", task.status),
+        format!("Priority: {}", task.priority_level),
+        format!("Due Date: {}", task.due()),
+        format!("Time Remaining: {}", format_duration(task.time_remaining)),
+        format!("Time Spent: {}", format_duration(task.elapsed_time)),
+    ];
+    match (task.minimum_chunk_size, task.maximum_chunk_size) {
+        (None, None) => {}
+        (minimum, maximum) => lines.push(format!(
+            "Work Sessions: {} to {}",
+            minimum.map_or("any".to_string(), format_duration),
+            maximum.map_or("any".to_string(), format_duration)
+        )),
+    }
+    if let Some(project) = &task.project {
+        lines.push(format!("Project: {}", project));
+    }
+    if !task.tags.is_empty() {
+        let tags: Vec<String> = task.tags.iter().map(|tag| format!("#{}", tag)).collect();
+        lines.push(format!("Tags: {}", tags.join(" ")));
+    }
+    if let Some(recurrence) = &task.recurrence {
+        lines.push(format!("Repeats: {}", recurrence));
+    }
+    if let Some(parent) = task.parent {
+        lines.push(format!(
+            "Parent: {}",
+            labels(&mut std::iter::once(parent))[0]
+        ));
+    }
+    let children = labels(&mut subtasks(task, tasks).into_iter().map(|child| child.id));
+    if !children.is_empty() {
+        lines.push(format!("Subtasks: {}", children.join(", ")));
+    }
+    if !task.depends_on.is_empty() {
+        lines.push(format!(
+            "Depends On: {}",
+            labels(&mut task.depends_on.iter().copied()).join(", ")
+        ));
+    }
+    lines.push(format!("Created: {}", local(&task.created_date)));
+
+    if let Some(description) = &task.description {
+        lines.push("Description:".to_string());
+        lines.extend(description.lines().map(|line| format!("    {}", line)));
+    }
+    if !task.links.is_empty() {
+        lines.push("Links:".to_string());
+        lines.extend(task.links.iter().map(|link| format!("    {}", link)));
+    }
+    if !task.annotations.is_empty() {
+        lines.push("Notes:".to_string());
+        for annotation in &task.annotations {
+            // Later lines of a note line up under the first
+            let mut text = annotation.text.lines();
+            lines.push(format!(
+                "    {}  {}",
+                local(&annotation.at),
+                text.next().unwrap_or_default()
+            ));
+            lines.extend(text.map(|line| format!("    {:16}  {}", "", line)));
+        }
+    }
+
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
 pub fn filter_out_completed_tasks(tasks: &[Task]) -> Result<Vec<Task>> {
     let filtered_tasks: Vec<Task> = tasks
         .iter()
@@ -33,11 +142,15 @@ pub fn filter_out_completed_tasks(tasks: &[Task]) -> Result<Vec<Task>> {
     Ok(filtered_tasks)
 }
 
+/// Prints `tasks` as a tree, each subtask indented under its parent.
 pub fn list_tasks(tasks: &[Task], count: Option<u32>) -> Result<()> {
     let num_to_display = count.map(|c| c as usize).unwrap_or(tasks.len());
 
-    for task in tasks.iter().take(num_to_display) {
-        println!("{}", task);
+    for (depth, task) in task_tree(tasks).into_iter().take(num_to_display) {
+        let indent = "    ".repeat(depth);
+        for line in task.to_string().lines() {
+            println!("{}{}", indent, line);
+        }
     }
 
     Ok(())
@@ -46,44 +159,33 @@ pub fn list_tasks(tasks: &[Task], count: Option<u32>) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::sample_task;
 
     fn create_sample_tasks() -> Vec<Task> {
+        let working = vec![(Utc::now(), None)];
         vec![
             Task {
-                id: Uuid::new_v4(),
-                name: "Complete Rust project".to_string(),
                 time_remaining: 120,
                 due_date: NaiveDate::from_ymd_opt(2024, 12, 1).unwrap(),
                 status: Status::InProgress,
-                created_date: Utc::now(),
                 priority_level: Priority::High,
-                minimum_chunk_size: None,
-                elapsed_time: 0,
-                work_intervals: vec![(chrono::Utc::now(), None)],
+                work_intervals: working.clone(),
+                ..sample_task("Complete Rust project")
             },
             Task {
-                id: Uuid::new_v4(),
-                name: "Read documentation".to_string(),
                 time_remaining: 60,
                 due_date: NaiveDate::from_ymd_opt(2024, 11, 25).unwrap(),
-                status: Status::UnStarted,
-                created_date: Utc::now(),
-                priority_level: Priority::Medium,
-                minimum_chunk_size: None,
-                elapsed_time: 0,
-                work_intervals: vec![(chrono::Utc::now(), None)],
+                work_intervals: working.clone(),
+                ..sample_task("Read documentation")
             },
             Task {
-                id: Uuid::new_v4(),
-                name: "Prepare presentation".to_string(),
                 time_remaining: 90,
                 due_date: NaiveDate::from_ymd_opt(2024, 11, 29).unwrap(),
                 status: Status::Completed,
-                created_date: Utc::now(),
                 priority_level: Priority::Urgent,
                 minimum_chunk_size: Some(30),
-                elapsed_time: 0,
-                work_intervals: vec![(chrono::Utc::now(), None)],
+                work_intervals: working,
+                ..sample_task("Prepare presentation")
             },
         ]
     }
@@ -96,4 +198,24 @@ mod tests {
         assert_eq!(result.len(), 2); // Only 2 tasks are not completed
         assert!(result.iter().all(|task| task.status != Status::Completed));
     }
+
+    #[test]
+    fn details_include_notes_and_related_tasks() {
+        let mut tasks = create_sample_tasks();
+        tasks[1].parent = Some(tasks[0].id);
+        tasks[0].depends_on = vec![tasks[2].id];
+        tasks[0].description = Some("First line\nSecond line".to_string());
+        tasks[0].links = vec!["https://example.com/42".to_string()];
+        tasks[0].annotate("Waiting on review").unwrap();
+
+        let details = task_details(&tasks[0], &tasks, Zone::Local);
+
+        assert!(details.contains("Subtasks: Read documentation ("));
+        assert!(details.contains("Depends On: Prepare presentation ("));
+        assert!(details.contains("[Completed]"));
+        assert!(details.contains("Description:\n    First line\n    Second line\n"));
+        assert!(details.contains("Links:\n    https://example.com/42\n"));
+        assert!(details.contains("  Waiting on review\n"));
+        assert!(!details.contains("Work Sessions"));
+    }
 }
@@ -1,7 +1,7 @@
 use crate::prelude::*;
 
-pub fn update_status(task: &mut Task, status: Status) {
-    task.status = status;
+pub fn update_status(task: &mut Task, status: Status) -> Result<()> {
+    task.transition_to(status)
 }
 
 pub fn update_task_in_list(tasks: &mut [Task], updated_task: Task) -> Result<()> {
@@ -13,27 +13,147 @@ pub fn update_task_in_list(tasks: &mut [Task], updated_task: Task) -> Result<()>
     }
 }
 
-pub fn edit_task(old_task: &Task, payload: &TaskEditPayload) -> Result<Task> {
-    let due_date = payload
+/// `workday_minutes` is how long a day is in durations like `1d`, `today` is
+/// what relative dates count from, and `tasks` are the ones new dependencies
+/// and parents are looked up in.
+pub fn edit_task(
+    old_task: &Task,
+    payload: &TaskEditPayload,
+    workday_minutes: u32,
+    today: NaiveDate,
+    tasks: &[Task],
+) -> Result<Task> {
+    let minutes = |duration: Option<HumanDuration>| {
+        duration
+            .map(|duration| duration.to_minutes(workday_minutes))
+            .transpose()
+    };
+
+    let due = payload
         .due_date
         .as_ref()
-        .and_then(|date_str| NaiveDate::parse_from_str(date_str, "%Y-%m-%d").ok());
+        .map(|input| parse_due_date(input, today))
+        .transpose()?
+        .unwrap_or(old_task.due());
 
     let status = Status::from_option(payload.status.clone())?;
     let priority = parse_priority(payload.priority.clone());
 
-    let task = Task {
+    let mut task = Task {
         id: old_task.id,
         name: payload.name.clone().unwrap_or(old_task.name.clone()),
-        time_remaining: payload.time_remaining.unwrap_or(old_task.time_remaining),
-        due_date: due_date.unwrap_or(old_task.due_date),
-        status: status.unwrap_or(old_task.status.clone()),
+        time_remaining: minutes(payload.time_remaining)?.unwrap_or(old_task.time_remaining),
+        due_date: due.date,
+        due_time: due.time,
+        depends_on: old_task.depends_on.clone(),
+        parent: old_task.parent,
+        recurrence: old_task.recurrence.clone(),
+        tags: old_task.tags.clone(),
+        project: old_task.project.clone(),
+        description: old_task.description.clone(),
+        links: old_task.links.clone(),
+        annotations: old_task.annotations.clone(),
+        status: old_task.status.clone(),
         created_date: old_task.created_date,
         priority_level: priority.unwrap_or(old_task.priority_level.clone()),
-        minimum_chunk_size: payload.minimum_chunk_size.or(old_task.minimum_chunk_size),
+        minimum_chunk_size: minutes(payload.minimum_chunk_size)?.or(old_task.minimum_chunk_size),
+        maximum_chunk_size: minutes(payload.maximum_chunk_size)?.or(old_task.maximum_chunk_size),
         work_intervals: old_task.work_intervals.clone(),
-        elapsed_time: payload.elapsed_time.unwrap_or(old_task.elapsed_time),
+        status_history: old_task.status_history.clone(),
+        elapsed_time: minutes(payload.elapsed_time)?.unwrap_or(old_task.elapsed_time),
     };
 
+    for prefix in &payload.remove_dependencies {
+        let before = task.depends_on.len();
+        task.depends_on
+            .retain(|id| !id.to_string().starts_with(prefix.as_str()));
+        if task.depends_on.len() == before {
+            return Err(Error::Generic(format!(
+                "Task does not depend on {}",
+                prefix
+            )));
+        }
+    }
+    for id in resolve_dependencies(&payload.add_dependencies, tasks)? {
+        if !task.depends_on.contains(&id) {
+            task.depends_on.push(id);
+        }
+    }
+
+    if payload.remove_parent {
+        task.parent = None;
+    }
+    if let Some(prefix) = &payload.parent {
+        task.parent = Some(resolve_parent(prefix, tasks)?);
+    }
+
+    if payload.remove_repeat {
+        task.recurrence = None;
+    }
+    if let Some(repeat) = &payload.repeat {
+        // A new rule keeps the limits of the old one unless new ones are given
+        let old = task.recurrence.take();
+        task.recurrence = Some(Recurrence {
+            count: old.as_ref().and_then(|old| old.count),
+            until: old.as_ref().and_then(|old| old.until),
+            ..repeat.clone()
+        });
+    }
+    if payload.due_date.is_some() {
+        // A monthly series follows the day of the new due date from now on
+        if let Some(recurrence) = task.recurrence.as_mut() {
+            recurrence.day_of_month = None;
+        }
+    }
+    let repeat_until = payload
+        .repeat_until
+        .as_ref()
+        .map(|input| parse_due_date(input, today))
+        .transpose()?
+        .map(|due| due.date);
+    if payload.repeat_count.is_some() || repeat_until.is_some() {
+        task.recurrence
+            .as_mut()
+            .ok_or_else(|| {
+                Error::Generic("Task does not repeat; give it a rule with --repeat".to_string())
+            })?
+            .limit(payload.repeat_count, repeat_until)?;
+    }
+
+    let remove_tags = normalize_tags(&payload.remove_tags)?;
+    task.tags.retain(|tag| !remove_tags.contains(tag));
+    for tag in normalize_tags(&payload.add_tags)? {
+        if !task.tags.contains(&tag) {
+            task.tags.push(tag);
+        }
+    }
+    if payload.remove_project {
+        task.project = None;
+    }
+    if let Some(project) = &payload.project {
+        task.project = Some(normalize_project(project)?);
+    }
+
+    if let Some(description) = &payload.description {
+        task.description = normalize_description(description);
+    }
+    for link in &payload.remove_links {
+        let before = task.links.len();
+        task.links.retain(|other| other != link.trim());
+        if task.links.len() == before {
+            return Err(Error::Generic(format!("Task has no link {}", link)));
+        }
+    }
+    for link in &payload.add_links {
+        let link = normalize_link(link)?;
+        if !task.links.contains(&link) {
+            task.links.push(link);
+        }
+    }
+
+    if let Some(status) = status {
+        task.transition_to(status)?;
+    }
+
     Ok(task)
 }
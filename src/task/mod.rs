@@ -1,10 +1,15 @@
 use crate::prelude::*;
-use core::panic;
 use std::str::FromStr;
 
 pub mod create;
+pub mod dependency;
 pub mod display;
 pub mod edit;
+pub mod note;
+pub mod recurrence;
+pub mod status;
+pub mod subtask;
+pub mod tag;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Task {
@@ -13,21 +18,89 @@ pub struct Task {
     pub time_remaining: u32,
     pub elapsed_time: u32,
     pub due_date: NaiveDate,
+    /// Time of day the task is due; without one it is due by the end of the day
+    #[serde(default)]
+    pub due_time: Option<NaiveTime>,
     pub status: Status,
     pub created_date: DateTime<Utc>,
     pub priority_level: Priority,
     pub minimum_chunk_size: Option<u32>,
+    #[serde(default)]
+    pub maximum_chunk_size: Option<u32>,
     pub work_intervals: Vec<(DateTime<Utc>, Option<DateTime<Utc>>)>,
+    #[serde(default)]
+    pub status_history: Vec<StatusChange>,
+    /// Tasks that must be finished before this one can start
+    #[serde(default)]
+    pub depends_on: Vec<Uuid>,
+    /// Task this one is a subtask of
+    #[serde(default)]
+    pub parent: Option<Uuid>,
+    /// How the task repeats once completed
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// URLs or file paths with more context
+    #[serde(default)]
+    pub links: Vec<String>,
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+}
+
+pub struct TaskCreatePayload {
+    pub name: String,
+    pub time_remaining: HumanDuration,
+    pub due_date: String,
+    pub priority: Option<String>,
+    pub minimum_chunk_size: Option<HumanDuration>,
+    pub maximum_chunk_size: Option<HumanDuration>,
+    /// Id prefixes of tasks that must be finished first
+    pub depends_on: Vec<String>,
+    /// Id prefix of the task this one is a subtask of
+    pub parent: Option<String>,
+    pub repeat: Option<Recurrence>,
+    pub repeat_count: Option<u32>,
+    pub repeat_until: Option<String>,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    pub description: Option<String>,
+    pub links: Vec<String>,
 }
 
 pub struct TaskEditPayload {
     pub name: Option<String>,
-    pub time_remaining: Option<u32>,
+    pub time_remaining: Option<HumanDuration>,
     pub due_date: Option<String>,
     pub status: Option<String>,
     pub priority: Option<String>,
-    pub minimum_chunk_size: Option<u32>,
-    pub elapsed_time: Option<u32>,
+    pub minimum_chunk_size: Option<HumanDuration>,
+    pub maximum_chunk_size: Option<HumanDuration>,
+    pub elapsed_time: Option<HumanDuration>,
+    /// Id prefixes of tasks to start or stop depending on
+    pub add_dependencies: Vec<String>,
+    pub remove_dependencies: Vec<String>,
+    /// Id prefix of the new parent task
+    pub parent: Option<String>,
+    pub remove_parent: bool,
+    pub repeat: Option<Recurrence>,
+    pub repeat_count: Option<u32>,
+    pub repeat_until: Option<String>,
+    pub remove_repeat: bool,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+    pub project: Option<String>,
+    pub remove_project: bool,
+    /// Replaces the description; an empty one removes it
+    pub description: Option<String>,
+    /// Open the description in `$EDITOR`
+    pub edit_description: bool,
+    pub add_links: Vec<String>,
+    pub remove_links: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -71,20 +144,6 @@ impl Status {
     }
 }
 
-impl Priority {
-    fn from_input(input: Option<String>) -> Self {
-        match input
-            .and_then(|s| s.parse::<u8>().ok()) // Parse input to `u8` if provided
-        {
-            Some(1) => Priority::Urgent,
-            Some(2) => Priority::High,
-            Some(3) => Priority::Medium,
-            Some(4) => Priority::Low,
-            _ => Priority::Medium, // Default to Medium
-        }
-    }
-}
-
 pub fn parse_priority(input: Option<String>) -> Option<Priority> {
     input.and_then(|s| match s.parse::<u8>() {
         Ok(1) => Some(Priority::Urgent),
@@ -100,13 +159,15 @@ pub fn schedule_tasks(tasks: &mut [Task]) {
         // First compare by priority level (ascending)
         a.priority_level
             .cmp(&b.priority_level)
-            // Then compare by due date if priority levels are equal
-            .then_with(|| a.due_date.cmp(&b.due_date))
+            // Then compare by deadline if priority levels are equal
+            .then_with(|| deadline(a).cmp(&deadline(b)))
     });
+    // Prerequisites go ahead of the tasks waiting on them
+    order_by_dependencies(tasks);
 }
 
 pub fn get_task(tasks: &[Task], search_string: &str) -> Option<Task> {
-    let mut matching_tasks = tasks
+    let matching_tasks = tasks
         .iter()
         .filter(|task| task.id.to_string().starts_with(search_string))
         .collect::<Vec<_>>();
@@ -119,37 +180,72 @@ pub fn get_task(tasks: &[Task], search_string: &str) -> Option<Task> {
 }
 
 pub fn chunks_remaining(task: &Task) -> u32 {
-    task.minimum_chunk_size
-        .map_or(0, |chunk_size| task.time_remaining / chunk_size)
-        + 1
+    split_into_chunks(task.time_remaining, task.minimum_chunk_size).len() as u32
 }
 
 impl Task {
-    pub fn start_work(&mut self) {
-        if self.status == Status::InProgress {
-            panic!("Task is already in progress");
+    pub fn start_work(&mut self) -> Result<()> {
+        match self.status {
+            Status::InProgress => return Err(Error::AlreadyInProgress),
+            Status::Completed => return Err(Error::TaskCompleted),
+            Status::Deleted => return Err(Error::TaskDeleted),
+            Status::UnStarted | Status::OnHold => {}
         }
-        self.status = Status::InProgress;
-        self.work_intervals.push((Utc::now(), None));
+        self.transition_to(Status::InProgress)
     }
-    pub fn stop_work(&mut self) {
+    pub fn stop_work(&mut self) -> Result<()> {
         if self.status != Status::InProgress {
-            panic!("Task is not currently in progress");
+            return Err(Error::NotInProgress);
         }
-        self.status = Status::OnHold;
-
-        if let Some((start, end_time)) = self.work_intervals.last_mut() {
-            let now = Utc::now();
-            *end_time = Some(now);
-
-            let elapsed_minutes = (now - *start).num_minutes() as u32;
+        self.transition_to(Status::OnHold)
+    }
 
-            self.elapsed_time += elapsed_minutes;
-            self.time_remaining = self.time_remaining.saturating_sub(elapsed_minutes);
+    pub fn due(&self) -> DueDate {
+        DueDate {
+            date: self.due_date,
+            time: self.due_time,
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    #[test]
+    fn start_and_stop_work() {
+        let mut task = Task {
+            status: Status::UnStarted,
+            time_remaining: 60,
+            ..sample_task("Sample")
+        };
+
+        task.start_work().unwrap();
+        assert_eq!(task.status, Status::InProgress);
+        assert!(matches!(task.start_work(), Err(Error::AlreadyInProgress)));
+
+        task.stop_work().unwrap();
+        assert_eq!(task.status, Status::OnHold);
+        assert!(task.work_intervals[0].1.is_some());
+        assert!(matches!(task.stop_work(), Err(Error::NotInProgress)));
+    }
 
-    pub fn is_complete(&self) -> bool {
-        self.time_remaining == 0
+    #[test]
+    fn cannot_start_finished_tasks() {
+        let mut completed = Task {
+            status: Status::Completed,
+            time_remaining: 60,
+            ..sample_task("Sample")
+        };
+        let mut deleted = Task {
+            status: Status::Deleted,
+            time_remaining: 60,
+            ..sample_task("Sample")
+        };
+
+        assert!(matches!(completed.start_work(), Err(Error::TaskCompleted)));
+        assert!(matches!(deleted.start_work(), Err(Error::TaskDeleted)));
+        assert!(completed.work_intervals.is_empty());
     }
 }
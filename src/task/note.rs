@@ -0,0 +1,69 @@
+use crate::prelude::*;
+
+/// A timestamped note added with `annotate`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Annotation {
+    pub at: DateTime<Utc>,
+    pub text: String,
+}
+
+impl Task {
+    /// Appends a note stamped with the current time.
+    pub fn annotate(&mut self, text: &str) -> Result<()> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(Error::Generic("Note cannot be empty".to_string()));
+        }
+        self.annotations.push(Annotation {
+            at: Utc::now(),
+            text: text.to_string(),
+        });
+        Ok(())
+    }
+}
+
+/// An empty description means no description.
+pub fn normalize_description(input: &str) -> Option<String> {
+    let description = input.trim_end();
+    let description = description.trim_start_matches(['\n', '\r']);
+    (!description.is_empty()).then(|| description.to_string())
+}
+
+pub fn normalize_link(input: &str) -> Result<String> {
+    let link = input.trim();
+    if link.is_empty() || link.contains(char::is_whitespace) {
+        return Err(Error::Generic(format!(
+            "Invalid link: {}. Expected a URL or file path without spaces",
+            input
+        )));
+    }
+    Ok(link.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    #[test]
+    fn appends_timestamped_notes() {
+        let mut task = sample_task("report");
+
+        task.annotate("  sent draft to Sam\n").unwrap();
+        task.annotate("waiting on figures").unwrap();
+
+        assert_eq!(task.annotations.len(), 2);
+        assert_eq!(task.annotations[0].text, "sent draft to Sam");
+        assert!(task.annotations[0].at <= task.annotations[1].at);
+        assert!(task.annotate(" \n ").is_err());
+    }
+
+    #[test]
+    fn blank_descriptions_are_removed() {
+        assert_eq!(normalize_description("\n  \n"), None);
+        assert_eq!(
+            normalize_description("\nfirst\n  second\n\n").as_deref(),
+            Some("first\n  second")
+        );
+    }
+}
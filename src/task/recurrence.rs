@@ -0,0 +1,329 @@
+use crate::prelude::*;
+use chrono::{Datelike, Days, Months, NaiveDateTime};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// How a task repeats: every `interval` days, weeks or months, optionally
+/// ending after a number of occurrences or at a date.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    /// Occurrences left, this one included
+    #[serde(default)]
+    pub count: Option<u32>,
+    /// Last date an occurrence may be due
+    #[serde(default)]
+    pub until: Option<NaiveDate>,
+    /// Day of the month monthly series fall due on, kept so a series that
+    /// starts on the 31st returns to it after shorter months
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day_of_month: Option<u32>,
+}
+
+impl Recurrence {
+    /// Ends the series after `count` occurrences, this one included, or after
+    /// the date `until`. Limits that aren't given stay as they were.
+    pub fn limit(&mut self, count: Option<u32>, until: Option<NaiveDate>) -> Result<()> {
+        if count == Some(0) {
+            return Err(Error::Generic(
+                "A repeating task needs at least one occurrence".to_string(),
+            ));
+        }
+        self.count = count.or(self.count);
+        self.until = until.or(self.until);
+        Ok(())
+    }
+
+    fn after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self.frequency {
+            Frequency::Daily => date.checked_add_days(Days::new(self.interval as u64)),
+            Frequency::Weekly => date.checked_add_days(Days::new(self.interval as u64 * 7)),
+            Frequency::Monthly => {
+                self.anchored(date.checked_add_months(Months::new(self.interval))?)
+            }
+        }
+    }
+
+    fn before(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self.frequency {
+            Frequency::Daily => date.checked_sub_days(Days::new(self.interval as u64)),
+            Frequency::Weekly => date.checked_sub_days(Days::new(self.interval as u64 * 7)),
+            Frequency::Monthly => {
+                self.anchored(date.checked_sub_months(Months::new(self.interval))?)
+            }
+        }
+    }
+
+    /// Moves `date` to the anchor day, or the last day of its month when the
+    /// month is shorter.
+    fn anchored(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self.day_of_month {
+            Some(day) => (1..=day).rev().find_map(|day| date.with_day(day)),
+            None => Some(date),
+        }
+    }
+}
+
+/// Reads `daily`, `weekly`, `monthly`, `yearly`, `every 2 weeks`, `every day`
+/// or the short form `3d`, `2w`, `1m`.
+impl FromStr for Recurrence {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            Error::Generic(format!(
+                "Could not parse the repeat rule: {}. Expected daily, weekly, monthly, yearly, every 2 weeks or 3d",
+                s
+            ))
+        };
+        let unit = |word: &str| match word {
+            "d" | "day" | "days" => Some(Frequency::Daily),
+            "w" | "week" | "weeks" => Some(Frequency::Weekly),
+            "m" | "month" | "months" => Some(Frequency::Monthly),
+            _ => None,
+        };
+
+        let input = s.trim().to_lowercase();
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let (interval, frequency) = match words.as_slice() {
+            ["daily"] => (1, Frequency::Daily),
+            ["weekly"] => (1, Frequency::Weekly),
+            ["monthly"] => (1, Frequency::Monthly),
+            ["yearly" | "annually"] => (12, Frequency::Monthly),
+            ["every", word] => (1, unit(word).ok_or_else(invalid)?),
+            ["every", amount, word] => (
+                amount.parse().map_err(|_| invalid())?,
+                unit(word).ok_or_else(invalid)?,
+            ),
+            [short] => {
+                let split = short
+                    .find(|c: char| !c.is_ascii_digit())
+                    .ok_or_else(invalid)?;
+                (
+                    short[..split].parse().map_err(|_| invalid())?,
+                    unit(&short[split..]).ok_or_else(invalid)?,
+                )
+            }
+            _ => return Err(invalid()),
+        };
+        if interval == 0 {
+            return Err(invalid());
+        }
+
+        Ok(Recurrence {
+            frequency,
+            interval,
+            count: None,
+            until: None,
+            day_of_month: None,
+        })
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.frequency, self.interval) {
+            (Frequency::Daily, 1) => write!(f, "daily")?,
+            (Frequency::Weekly, 1) => write!(f, "weekly")?,
+            (Frequency::Monthly, 1) => write!(f, "monthly")?,
+            (Frequency::Monthly, 12) => write!(f, "yearly")?,
+            (Frequency::Daily, n) => write!(f, "every {} days", n)?,
+            (Frequency::Weekly, n) => write!(f, "every {} weeks", n)?,
+            (Frequency::Monthly, n) => write!(f, "every {} months", n)?,
+        }
+        if let Some(count) = self.count {
+            write!(f, ", {} left", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ", until {}", until)?;
+        }
+        Ok(())
+    }
+}
+
+/// The occurrence after `task`, due one interval later, or `None` once the
+/// series is over. It gets as much time as `task` took or was estimated at.
+pub fn next_occurrence(task: &Task) -> Option<Task> {
+    let recurrence = task.recurrence.as_ref()?;
+    if recurrence.count.is_some_and(|count| count <= 1) {
+        return None;
+    }
+    let recurrence = Recurrence {
+        count: recurrence.count.map(|count| count - 1),
+        day_of_month: match recurrence.frequency {
+            Frequency::Monthly => recurrence.day_of_month.or(Some(task.due_date.day())),
+            _ => None,
+        },
+        ..recurrence.clone()
+    };
+    let due_date = recurrence.after(task.due_date)?;
+    if recurrence.until.is_some_and(|until| due_date > until) {
+        return None;
+    }
+
+    Some(Task {
+        id: next_occurrence_id(task.id),
+        name: task.name.clone(),
+        time_remaining: task.time_remaining + task.elapsed_time,
+        elapsed_time: 0,
+        due_date,
+        due_time: task.due_time,
+        status: Status::UnStarted,
+        created_date: Utc::now(),
+        priority_level: task.priority_level.clone(),
+        minimum_chunk_size: task.minimum_chunk_size,
+        maximum_chunk_size: task.maximum_chunk_size,
+        work_intervals: Vec::new(),
+        status_history: Vec::new(),
+        depends_on: task.depends_on.clone(),
+        parent: task.parent,
+        recurrence: Some(recurrence),
+        tags: task.tags.clone(),
+        project: task.project.clone(),
+        description: task.description.clone(),
+        links: task.links.clone(),
+        annotations: Vec::new(),
+    })
+}
+
+/// The occurrences after `task` that fall due by `until`, so they can be
+/// planned before they exist.
+pub fn upcoming_occurrences(task: &Task, until: NaiveDate) -> Vec<Task> {
+    let mut upcoming: Vec<Task> = Vec::new();
+    while let Some(next) = next_occurrence(upcoming.last().unwrap_or(task)) {
+        if next.due_date > until {
+            break;
+        }
+        upcoming.push(next);
+    }
+    upcoming
+}
+
+/// Work on an occurrence only starts once the one before it was due.
+pub fn available_from(task: &Task) -> Option<NaiveDateTime> {
+    let previous = task.recurrence.as_ref()?.before(task.due_date)?;
+    match task.due_time {
+        Some(time) => Some(previous.and_time(time)),
+        None => previous.succ_opt()?.and_hms_opt(0, 0, 0),
+    }
+}
+
+/// Derived from the previous id rather than random, so an occurrence keeps
+/// the id it was planned under once it is actually created.
+fn next_occurrence_id(id: Uuid) -> Uuid {
+    let bytes = id
+        .as_u128()
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15_F39C_C060_5CED_C835)
+        .wrapping_add(1)
+        .to_be_bytes();
+    uuid::Builder::from_custom_bytes(bytes).into_uuid()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn repeating(rule: &str) -> Task {
+        let mut task = sample_task("report");
+        task.due_date = ymd(2024, 12, 6);
+        task.recurrence = Some(rule.parse().unwrap());
+        task
+    }
+
+    #[test]
+    fn parses_repeat_rules() {
+        let rule = |input: &str| input.parse::<Recurrence>().unwrap().to_string();
+
+        assert_eq!(rule("Daily"), "daily");
+        assert_eq!(rule("every week"), "weekly");
+        assert_eq!(rule("every 2 weeks"), "every 2 weeks");
+        assert_eq!(rule("3d"), "every 3 days");
+        assert_eq!(rule("yearly"), "yearly");
+        for input in ["", "sometimes", "every 0 days", "every two weeks", "2x"] {
+            assert!(input.parse::<Recurrence>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn completing_starts_the_next_occurrence() {
+        let mut task = repeating("monthly");
+        task.time_remaining = 15;
+        task.elapsed_time = 45;
+        task.status = Status::Completed;
+
+        let next = next_occurrence(&task).unwrap();
+
+        assert_ne!(next.id, task.id);
+        assert_eq!(next.id, next_occurrence(&task).unwrap().id);
+        assert_eq!(next.due_date, ymd(2025, 1, 6));
+        assert_eq!(next.time_remaining, 60);
+        assert_eq!(next.elapsed_time, 0);
+        assert_eq!(next.status, Status::UnStarted);
+    }
+
+    #[test]
+    fn series_end_after_a_count_or_date() {
+        let mut counted = repeating("weekly");
+        counted.recurrence.as_mut().unwrap().count = Some(2);
+        let next = next_occurrence(&counted).unwrap();
+        assert_eq!(next.recurrence.as_ref().unwrap().count, Some(1));
+        assert!(next_occurrence(&next).is_none());
+
+        let mut dated = repeating("daily");
+        dated.recurrence.as_mut().unwrap().until = Some(ymd(2024, 12, 8));
+        assert_eq!(upcoming_occurrences(&dated, ymd(2025, 1, 1)).len(), 2);
+    }
+
+    #[test]
+    fn monthly_series_keep_their_day_of_the_month() {
+        let mut task = repeating("monthly");
+        task.due_date = ymd(2024, 1, 31);
+
+        let upcoming = upcoming_occurrences(&task, ymd(2024, 5, 31));
+
+        let dates: Vec<NaiveDate> = upcoming.iter().map(|task| task.due_date).collect();
+        assert_eq!(
+            dates,
+            vec![
+                ymd(2024, 2, 29),
+                ymd(2024, 3, 31),
+                ymd(2024, 4, 30),
+                ymd(2024, 5, 31)
+            ]
+        );
+        assert_eq!(
+            available_from(&upcoming[1]),
+            ymd(2024, 3, 1).and_hms_opt(0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn plans_occurrences_within_the_horizon() {
+        let task = repeating("weekly");
+
+        let upcoming = upcoming_occurrences(&task, ymd(2024, 12, 31));
+
+        let dates: Vec<NaiveDate> = upcoming.iter().map(|task| task.due_date).collect();
+        assert_eq!(
+            dates,
+            vec![ymd(2024, 12, 13), ymd(2024, 12, 20), ymd(2024, 12, 27)]
+        );
+        assert_eq!(
+            available_from(&upcoming[0]),
+            ymd(2024, 12, 7).and_hms_opt(0, 0, 0)
+        );
+    }
+}
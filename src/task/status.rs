@@ -0,0 +1,131 @@
+use crate::prelude::*;
+
+/// One entry in a task's status audit trail.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StatusChange {
+    pub from: Status,
+    pub to: Status,
+    pub at: DateTime<Utc>,
+}
+
+impl Status {
+    /// The allowed moves between statuses. Completed tasks can only be
+    /// reopened (put on hold) or deleted, and deleted tasks can only be restored.
+    pub fn can_transition_to(&self, next: &Status) -> bool {
+        use Status::*;
+
+        matches!(
+            (self, next),
+            (UnStarted, InProgress | OnHold | Completed | Deleted)
+                | (InProgress, OnHold | Completed | Deleted)
+                | (OnHold, InProgress | Completed | Deleted)
+                | (Completed, OnHold | Deleted)
+                | (Deleted, UnStarted)
+        )
+    }
+}
+
+impl Task {
+    /// Moves the task to `next`, rejecting moves the state machine doesn't
+    /// allow. Entering InProgress opens a work interval and leaving it closes
+    /// the open one; every change is recorded in `status_history`.
+    pub fn transition_to(&mut self, next: Status) -> Result<()> {
+        if self.status == next {
+            return Ok(());
+        }
+        if !self.status.can_transition_to(&next) {
+            return Err(Error::InvalidTransition {
+                from: self.status.clone(),
+                to: next,
+            });
+        }
+
+        let now = Utc::now();
+        if self.status == Status::InProgress {
+            self.close_work_interval(now);
+        }
+        if next == Status::InProgress {
+            self.work_intervals.push((now, None));
+        }
+
+        self.status_history.push(StatusChange {
+            from: self.status.clone(),
+            to: next.clone(),
+            at: now,
+        });
+        self.status = next;
+        Ok(())
+    }
+
+    fn close_work_interval(&mut self, now: DateTime<Utc>) {
+        if let Some((start, end_time @ None)) = self.work_intervals.last_mut() {
+            *end_time = Some(now);
+
+            let elapsed_minutes = (now - *start).num_minutes().max(0) as u32;
+
+            self.elapsed_time += elapsed_minutes;
+            self.time_remaining = self.time_remaining.saturating_sub(elapsed_minutes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    #[test]
+    fn rejects_invalid_transitions() {
+        let mut task = Task {
+            status: Status::Deleted,
+            time_remaining: 60,
+            ..sample_task("Sample")
+        };
+
+        let result = task.transition_to(Status::InProgress);
+
+        assert!(matches!(result, Err(Error::InvalidTransition { .. })));
+        assert_eq!(task.status, Status::Deleted);
+        assert!(task.status_history.is_empty());
+    }
+
+    #[test]
+    fn completing_closes_the_open_work_interval() {
+        let mut task = Task {
+            status: Status::UnStarted,
+            time_remaining: 60,
+            ..sample_task("Sample")
+        };
+
+        task.transition_to(Status::InProgress).unwrap();
+        task.transition_to(Status::Completed).unwrap();
+
+        assert_eq!(task.work_intervals.len(), 1);
+        assert!(task.work_intervals[0].1.is_some());
+        let moves: Vec<(Status, Status)> = task
+            .status_history
+            .iter()
+            .map(|change| (change.from.clone(), change.to.clone()))
+            .collect();
+        assert_eq!(
+            moves,
+            vec![
+                (Status::UnStarted, Status::InProgress),
+                (Status::InProgress, Status::Completed)
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_status_is_not_recorded() {
+        let mut task = Task {
+            status: Status::Completed,
+            time_remaining: 60,
+            ..sample_task("Sample")
+        };
+
+        task.transition_to(Status::Completed).unwrap();
+
+        assert!(task.status_history.is_empty());
+    }
+}
@@ -0,0 +1,297 @@
+use crate::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Looks up the task named by the id `prefix` to use as a parent.
+pub fn resolve_parent(prefix: &str, tasks: &[Task]) -> Result<Uuid> {
+    get_task(tasks, prefix)
+        .map(|task| task.id)
+        .ok_or_else(|| Error::Generic(format!("No single task matches parent {}", prefix)))
+}
+
+/// The direct subtasks of `task`, in list order.
+pub fn subtasks<'a>(task: &Task, tasks: &'a [Task]) -> Vec<&'a Task> {
+    tasks
+        .iter()
+        .filter(|other| other.parent == Some(task.id))
+        .collect()
+}
+
+/// Ids of every task that has at least one subtask that isn't deleted.
+pub fn parent_ids(tasks: &[Task]) -> HashSet<Uuid> {
+    tasks
+        .iter()
+        .filter(|task| task.status != Status::Deleted)
+        .filter_map(|task| task.parent)
+        .collect()
+}
+
+/// Points dependencies on a parent at all of its subtasks as well, since the
+/// parent isn't done before they are. `all` is every task, for looking them up.
+pub fn expand_parent_dependencies(tasks: &mut [Task], all: &[Task]) {
+    let parents = parent_ids(all);
+    for task in tasks.iter_mut() {
+        if !task.depends_on.iter().any(|id| parents.contains(id)) {
+            continue;
+        }
+        let mut expanded = Vec::new();
+        let mut pending: Vec<Uuid> = task.depends_on.iter().rev().copied().collect();
+        while let Some(id) = pending.pop() {
+            if expanded.contains(&id) {
+                continue;
+            }
+            if parents.contains(&id) {
+                let children = all
+                    .iter()
+                    .filter(|t| t.parent == Some(id) && t.status != Status::Deleted);
+                pending.extend(children.map(|t| t.id));
+            }
+            expanded.push(id);
+        }
+        task.depends_on = expanded;
+    }
+}
+
+/// Fails if following `parent` from any task leads back to that task.
+pub fn check_subtasks(tasks: &[Task]) -> Result<()> {
+    let by_id: HashMap<Uuid, &Task> = tasks.iter().map(|task| (task.id, task)).collect();
+
+    for task in tasks {
+        let mut chain = vec![task];
+        let mut current = task;
+        while let Some(parent) = current.parent.and_then(|id| by_id.get(&id)) {
+            chain.push(parent);
+            if parent.id == task.id {
+                let labels: Vec<String> = chain.iter().map(|task| task.label()).collect();
+                return Err(Error::SubtaskCycle(labels.join(" -> ")));
+            }
+            if chain.len() > tasks.len() {
+                // A cycle further up, reported from one of its own tasks
+                break;
+            }
+            current = parent;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the time figures of every parent with totals over itself and its
+/// subtasks: `time_remaining` adds what its open subtasks still need to the
+/// parent's own remaining work, and `elapsed_time` adds the time spent on
+/// subtasks to any spent on the parent itself. Deleted subtasks don't count.
+pub fn roll_up_subtasks(tasks: &mut [Task]) {
+    let mut children: HashMap<Uuid, Vec<usize>> = HashMap::new();
+    for (i, task) in tasks.iter().enumerate() {
+        if let Some(parent) = task.parent.filter(|_| task.status != Status::Deleted) {
+            children.entry(parent).or_default().push(i);
+        }
+    }
+
+    let mut totals = HashMap::new();
+    for i in 0..tasks.len() {
+        total(i, tasks, &children, &mut totals, &mut HashSet::new());
+    }
+    for task in tasks.iter_mut() {
+        if children.contains_key(&task.id) {
+            (task.time_remaining, task.elapsed_time) = totals[&task.id];
+        }
+    }
+}
+
+fn total(
+    i: usize,
+    tasks: &[Task],
+    children: &HashMap<Uuid, Vec<usize>>,
+    totals: &mut HashMap<Uuid, (u32, u32)>,
+    visiting: &mut HashSet<Uuid>,
+) -> (u32, u32) {
+    let task = &tasks[i];
+    if let Some(total) = totals.get(&task.id) {
+        return *total;
+    }
+
+    let (mut remaining, mut elapsed) = (task.time_remaining, task.elapsed_time);
+    if visiting.insert(task.id) {
+        for &child in children.get(&task.id).into_iter().flatten() {
+            let (child_remaining, child_elapsed) = total(child, tasks, children, totals, visiting);
+            if tasks[child].status != Status::Completed {
+                remaining += child_remaining;
+            }
+            elapsed += child_elapsed;
+        }
+    }
+
+    totals.insert(task.id, (remaining, elapsed));
+    (remaining, elapsed)
+}
+
+/// The tasks in display order with their depth: each task is followed by its
+/// subtasks, and tasks whose parent isn't in the list are shown at the top level.
+pub fn task_tree(tasks: &[Task]) -> Vec<(usize, &Task)> {
+    let listed: HashSet<Uuid> = tasks.iter().map(|task| task.id).collect();
+    let mut tree = Vec::with_capacity(tasks.len());
+    let mut visited = HashSet::new();
+
+    for task in tasks {
+        if !task.parent.is_some_and(|parent| listed.contains(&parent)) {
+            add_branch(task, 0, tasks, &mut tree, &mut visited);
+        }
+    }
+    tree
+}
+
+fn add_branch<'a>(
+    task: &'a Task,
+    depth: usize,
+    tasks: &'a [Task],
+    tree: &mut Vec<(usize, &'a Task)>,
+    visited: &mut HashSet<Uuid>,
+) {
+    if !visited.insert(task.id) {
+        return;
+    }
+    tree.push((depth, task));
+    for child in subtasks(task, tasks) {
+        add_branch(child, depth + 1, tasks, tree, visited);
+    }
+}
+
+/// The parent of `task`, if it is still open and every one of its subtasks is
+/// now completed or deleted.
+pub fn finished_parent<'a>(task: &Task, tasks: &'a [Task]) -> Option<&'a Task> {
+    let parent = tasks.iter().find(|other| Some(other.id) == task.parent)?;
+    if matches!(parent.status, Status::Completed | Status::Deleted) {
+        return None;
+    }
+    subtasks(parent, tasks)
+        .iter()
+        .all(|child| matches!(child.status, Status::Completed | Status::Deleted))
+        .then_some(parent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::sample_task;
+
+    fn subtask(name: &str, parent: &Task, minutes: u32) -> Task {
+        let mut task = sample_task(name);
+        task.parent = Some(parent.id);
+        task.time_remaining = minutes;
+        task.elapsed_time = 10;
+        task
+    }
+
+    #[test]
+    fn rolls_time_up_from_subtasks() {
+        let mut project = sample_task("project");
+        project.elapsed_time = 5;
+        let draft = subtask("draft", &project, 60);
+        let mut edit = subtask("edit", &project, 30);
+        edit.status = Status::Completed;
+        let mut dropped = subtask("dropped", &project, 90);
+        dropped.status = Status::Deleted;
+        let figure = subtask("figure", &draft, 45);
+        let mut tasks = vec![project, draft, edit, dropped, figure];
+
+        roll_up_subtasks(&mut tasks);
+
+        // draft: its own 60 and 10 elapsed plus the figure's
+        assert_eq!((tasks[1].time_remaining, tasks[1].elapsed_time), (105, 20));
+        // project: its own 30 plus the draft's
+        assert_eq!((tasks[0].time_remaining, tasks[0].elapsed_time), (135, 35));
+        assert_eq!(tasks[4].time_remaining, 45);
+    }
+
+    #[test]
+    fn parents_keep_their_own_time() {
+        let mut project = sample_task("project");
+        project.time_remaining = 120;
+        let draft = subtask("draft", &project, 60);
+        let mut tasks = vec![project, draft];
+
+        roll_up_subtasks(&mut tasks);
+
+        assert_eq!(tasks[0].time_remaining, 180);
+    }
+
+    #[test]
+    fn parents_of_only_deleted_subtasks_are_plain_tasks() {
+        let mut project = sample_task("project");
+        project.time_remaining = 120;
+        let mut dropped = subtask("dropped", &project, 60);
+        dropped.status = Status::Deleted;
+        let mut tasks = vec![project.clone(), dropped];
+
+        roll_up_subtasks(&mut tasks);
+
+        assert!(parent_ids(&tasks).is_empty());
+        assert_eq!((tasks[0].time_remaining, tasks[0].elapsed_time), (120, 0));
+    }
+
+    #[test]
+    fn lists_subtasks_under_their_parents() {
+        let project = sample_task("project");
+        let other = sample_task("other");
+        let draft = subtask("draft", &project, 60);
+        let figure = subtask("figure", &draft, 45);
+        let tasks = vec![figure, other, draft, project];
+
+        let tree: Vec<(usize, &str)> = task_tree(&tasks)
+            .into_iter()
+            .map(|(depth, task)| (depth, task.name.as_str()))
+            .collect();
+
+        assert_eq!(
+            tree,
+            vec![(0, "other"), (0, "project"), (1, "draft"), (2, "figure")]
+        );
+    }
+
+    #[test]
+    fn rejects_tasks_nested_under_themselves() {
+        let mut project = sample_task("project");
+        let draft = subtask("draft", &project, 60);
+        assert!(check_subtasks(&[project.clone(), draft.clone()]).is_ok());
+
+        project.parent = Some(draft.id);
+        assert!(matches!(
+            check_subtasks(&[project, draft]),
+            Err(Error::SubtaskCycle(_))
+        ));
+    }
+
+    #[test]
+    fn dependencies_on_parents_wait_for_their_subtasks() {
+        let project = sample_task("project");
+        let draft = subtask("draft", &project, 60);
+        let figure = subtask("figure", &draft, 45);
+        let mut publish = sample_task("publish");
+        publish.depends_on = vec![project.id];
+        let all = vec![
+            project.clone(),
+            draft.clone(),
+            figure.clone(),
+            publish.clone(),
+        ];
+        let mut open = vec![publish];
+
+        expand_parent_dependencies(&mut open, &all);
+
+        assert!(open[0].depends_on.contains(&draft.id));
+        assert!(open[0].depends_on.contains(&figure.id));
+    }
+
+    #[test]
+    fn parents_finish_with_their_last_subtask() {
+        let project = sample_task("project");
+        let mut draft = subtask("draft", &project, 60);
+        let edit = subtask("edit", &project, 30);
+        draft.status = Status::Completed;
+        let mut tasks = vec![project, draft.clone(), edit];
+
+        assert!(finished_parent(&draft, &tasks).is_none());
+
+        tasks[2].status = Status::Deleted;
+        assert_eq!(finished_parent(&draft, &tasks).unwrap().name, "project");
+    }
+}
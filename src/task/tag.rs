@@ -0,0 +1,70 @@
+use crate::prelude::*;
+
+/// Tags are kept without the leading `#` and in lower case, so `#Work` and
+/// `work` are the same tag.
+pub fn normalize_tag(input: &str) -> Result<String> {
+    let tag = input.trim().trim_start_matches('#').to_lowercase();
+    if tag.is_empty() || tag.contains(|c: char| c.is_whitespace() || c == ',') {
+        return Err(Error::Generic(format!(
+            "Invalid tag: {}. Tags are single words like work or #personal",
+            input
+        )));
+    }
+    Ok(tag)
+}
+
+pub fn normalize_tags(inputs: &[String]) -> Result<Vec<String>> {
+    inputs.iter().map(|input| normalize_tag(input)).collect()
+}
+
+/// Project names keep their case but lose surrounding whitespace.
+pub fn normalize_project(input: &str) -> Result<String> {
+    let project = input.trim();
+    if project.is_empty() {
+        return Err(Error::Generic("Project name cannot be empty".to_string()));
+    }
+    Ok(project.to_string())
+}
+
+pub fn normalize_projects(inputs: &[String]) -> Result<Vec<String>> {
+    inputs
+        .iter()
+        .map(|input| normalize_project(input))
+        .collect()
+}
+
+impl Task {
+    /// Projects are compared ignoring case.
+    pub fn in_project(&self, project: &str) -> bool {
+        self.project
+            .as_ref()
+            .is_some_and(|own| own.eq_ignore_ascii_case(project))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_tags() {
+        assert_eq!(normalize_tag("#Work").unwrap(), "work");
+        assert_eq!(normalize_tag(" personal ").unwrap(), "personal");
+        for input in ["", "#", "two words", "a,b"] {
+            assert!(normalize_tag(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn project_filters_match_like_stored_projects() {
+        let task = Task {
+            project: Some(normalize_project(" Website ").unwrap()),
+            ..crate::test_support::sample_task("launch")
+        };
+
+        let filters = normalize_projects(&["website ".to_string()]).unwrap();
+
+        assert!(task.in_project(&filters[0]));
+        assert!(normalize_projects(&["  ".to_string()]).is_err());
+    }
+}
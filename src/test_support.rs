@@ -0,0 +1,31 @@
+//! Fixtures shared by the unit tests
+
+use crate::prelude::*;
+
+/// An unstarted, medium priority task needing 30 minutes, due 2024-12-31.
+/// Tests override the fields they care about with struct update syntax.
+pub fn sample_task(name: &str) -> Task {
+    Task {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        time_remaining: 30,
+        due_date: NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+        status: Status::UnStarted,
+        created_date: Utc::now(),
+        priority_level: Priority::Medium,
+        minimum_chunk_size: None,
+        maximum_chunk_size: None,
+        elapsed_time: 0,
+        work_intervals: Vec::new(),
+        status_history: Vec::new(),
+        due_time: None,
+        depends_on: Vec::new(),
+        parent: None,
+        recurrence: None,
+        tags: Vec::new(),
+        project: None,
+        description: None,
+        links: Vec::new(),
+        annotations: Vec::new(),
+    }
+}
@@ -0,0 +1,213 @@
+use crate::prelude::*;
+use chrono::{Datelike, Days, Months, NaiveTime, Weekday};
+use std::fmt;
+
+/// A due date with an optional time of day. Without a time the task is due
+/// by the end of the day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DueDate {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+}
+
+impl From<NaiveDate> for DueDate {
+    fn from(date: NaiveDate) -> Self {
+        DueDate { date, time: None }
+    }
+}
+
+impl fmt::Display for DueDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.time {
+            Some(time) => write!(f, "{} {}", self.date, time.format("%H:%M")),
+            None => write!(f, "{}", self.date),
+        }
+    }
+}
+
+/// Reads a date written as `YYYY-MM-DD`, `today`, `tomorrow`, a weekday
+/// (`friday`, the next one including today), `next monday` (in the following
+/// week), an offset like `+3d`, `+2w` or `+1m`, or `eow`/`eom`/`eoy` for the
+/// last day of the week, month or year. Any of these can be followed by a time
+/// such as `17:00` or `5pm`, and a time on its own means today.
+pub fn parse_due_date(input: &str, today: NaiveDate) -> Result<DueDate> {
+    let invalid = || {
+        Error::Generic(format!(
+            "Could not parse the date: {}. Expected YYYY-MM-DD, today, tomorrow, a weekday, next <weekday>, +3d, eow or eom, optionally followed by a time like 17:00",
+            input
+        ))
+    };
+
+    let mut normalized = input.trim().to_lowercase();
+    // `2024-12-01T17:00` is the ISO form of `2024-12-01 17:00`
+    if normalized.len() > 10
+        && normalized.as_bytes()[10] == b't'
+        && normalized[..10]
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b == b'-')
+    {
+        normalized.replace_range(10..11, " ");
+    }
+    let mut words: Vec<&str> = normalized.split_whitespace().collect();
+
+    let time = match words.last().and_then(|word| parse_time(word)) {
+        Some(time) => {
+            words.pop();
+            Some(time)
+        }
+        None => None,
+    };
+
+    let date = match words.as_slice() {
+        [] if time.is_some() => Some(today),
+        ["today" | "tod" | "eod"] => Some(today),
+        ["tomorrow" | "tom"] => today.checked_add_days(Days::new(1)),
+        ["eow"] => Some(next_weekday(today, Weekday::Sun)),
+        ["eom"] => end_of_month(today),
+        ["eoy"] => NaiveDate::from_ymd_opt(today.year(), 12, 31),
+        ["next", day] => {
+            let weekday: Weekday = day.parse().map_err(|_| invalid())?;
+            let next_week = today
+                .checked_add_days(Days::new(7 - today.weekday().num_days_from_monday() as u64));
+            next_week.map(|monday| next_weekday(monday, weekday))
+        }
+        [word] if word.starts_with('+') => parse_offset(&word[1..], today),
+        [word] => match word.parse::<Weekday>() {
+            Ok(weekday) => Some(next_weekday(today, weekday)),
+            Err(_) => NaiveDate::parse_from_str(word, "%Y-%m-%d").ok(),
+        },
+        _ => None,
+    };
+
+    date.map(|date| DueDate { date, time }).ok_or_else(invalid)
+}
+
+/// The first `weekday` on or after `from`.
+fn next_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (7 + weekday.num_days_from_monday() - from.weekday().num_days_from_monday()) % 7;
+    from + chrono::Duration::days(ahead as i64)
+}
+
+fn end_of_month(date: NaiveDate) -> Option<NaiveDate> {
+    let first = date.with_day(1)?;
+    first.checked_add_months(Months::new(1))?.pred_opt()
+}
+
+/// `3d`, `2w` or `1m`, counted from `today`.
+fn parse_offset(offset: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let unit = offset.chars().last()?;
+    let amount: u32 = offset[..offset.len() - unit.len_utf8()].parse().ok()?;
+    match unit {
+        'd' => today.checked_add_days(Days::new(amount as u64)),
+        'w' => today.checked_add_days(Days::new(amount as u64 * 7)),
+        'm' => today.checked_add_months(Months::new(amount)),
+        _ => None,
+    }
+}
+
+/// `17:00`, `9:30`, `17:00:00`, `5pm` or `5:30pm`.
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    let (clock, pm) = match (word.strip_suffix("am"), word.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, Some(false)),
+        (_, Some(clock)) => (clock, Some(true)),
+        _ => (word, None),
+    };
+
+    let mut parts = clock.split(':');
+    let hour: u32 = parts.next()?.parse().ok()?;
+    let minute: u32 = match parts.next() {
+        Some(minute) => minute.parse().ok()?,
+        // A bare number is a date or an offset, not a time
+        None if pm.is_some() => 0,
+        None => return None,
+    };
+    let second: u32 = match parts.next() {
+        Some(second) => second.parse().ok()?,
+        None => 0,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let hour = match pm {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(true) => hour % 12 + 12,
+        Some(false) => hour % 12,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A Wednesday
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 12, 4).unwrap()
+    }
+
+    fn date(input: &str) -> NaiveDate {
+        parse_due_date(input, today()).unwrap().date
+    }
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn parses_relative_dates() {
+        assert_eq!(date("2024-12-20"), ymd(2024, 12, 20));
+        assert_eq!(date("today"), today());
+        assert_eq!(date("Tomorrow"), ymd(2024, 12, 5));
+        assert_eq!(date("friday"), ymd(2024, 12, 6));
+        assert_eq!(date("wed"), today());
+        assert_eq!(date("monday"), ymd(2024, 12, 9));
+        assert_eq!(date("next monday"), ymd(2024, 12, 9));
+        assert_eq!(date("next friday"), ymd(2024, 12, 13));
+        assert_eq!(date("+3d"), ymd(2024, 12, 7));
+        assert_eq!(date("+2w"), ymd(2024, 12, 18));
+        assert_eq!(date("+1m"), ymd(2025, 1, 4));
+        assert_eq!(date("eow"), ymd(2024, 12, 8));
+        assert_eq!(date("eom"), ymd(2024, 12, 31));
+    }
+
+    #[test]
+    fn parses_optional_times() {
+        let at = |h, m| Some(NaiveTime::from_hms_opt(h, m, 0).unwrap());
+
+        assert_eq!(
+            parse_due_date("2024-12-01 17:00", today()).unwrap(),
+            DueDate {
+                date: ymd(2024, 12, 1),
+                time: at(17, 0)
+            }
+        );
+        assert_eq!(
+            parse_due_date("2024-12-01T09:30", today()).unwrap().time,
+            at(9, 30)
+        );
+        assert_eq!(
+            parse_due_date("tomorrow 5pm", today()).unwrap().time,
+            at(17, 0)
+        );
+        assert_eq!(parse_due_date("12:30am", today()).unwrap().time, at(0, 30));
+        assert_eq!(parse_due_date("friday", today()).unwrap().time, None);
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for input in [
+            "",
+            "someday",
+            "2024-13-01",
+            "next",
+            "next week",
+            "+3x",
+            "friday 25:00",
+            "13pm",
+        ] {
+            assert!(parse_due_date(input, today()).is_err(), "{}", input);
+        }
+    }
+}
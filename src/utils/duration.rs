@@ -0,0 +1,237 @@
+use crate::prelude::*;
+use std::fmt;
+use std::str::FromStr;
+
+/// Length of `1d` unless the config says otherwise.
+pub const DEFAULT_WORKDAY_MINUTES: u32 = 8 * 60;
+
+/// A duration as the user wrote it: `90`, `90m`, `1h30m`, `2.5h`, `1d` or
+/// ISO-8601 `PT1H30M`. Days are kept apart from the rest because a day means
+/// one workday, whose length comes from the config.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HumanDuration {
+    minutes: f64,
+    workdays: f64,
+}
+
+impl HumanDuration {
+    pub fn from_minutes(minutes: u32) -> Self {
+        HumanDuration {
+            minutes: minutes as f64,
+            workdays: 0.0,
+        }
+    }
+
+    /// Whole minutes, rounded to the nearest minute.
+    pub fn to_minutes(self, workday_minutes: u32) -> Result<u32> {
+        let total = (self.minutes + self.workdays * workday_minutes as f64).round();
+        if !(0.0..=u32::MAX as f64).contains(&total) {
+            return Err(Error::Generic(format!("Duration out of range: {}", self)));
+        }
+        Ok(total as u32)
+    }
+
+    fn add(&mut self, amount: f64, unit: &str) -> Result<()> {
+        match unit {
+            "d" | "day" | "days" => self.workdays += amount,
+            "h" | "hr" | "hrs" | "hour" | "hours" => self.minutes += amount * 60.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => self.minutes += amount,
+            "s" | "sec" | "secs" | "second" | "seconds" => self.minutes += amount / 60.0,
+            _ => return Err(Error::Generic(format!("Unknown duration unit: {}", unit))),
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for HumanDuration {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let input: String = s
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase();
+        let invalid = || {
+            Error::Generic(format!(
+                "Could not parse the duration: {}. Expected something like 90, 1h30m, 2.5h, 1d or PT1H30M",
+                s
+            ))
+        };
+
+        if input.is_empty() {
+            return Err(invalid());
+        }
+        // A bare number has always meant minutes
+        if let Ok(minutes) = input.parse::<f64>() {
+            return if minutes.is_finite() && minutes >= 0.0 {
+                Ok(HumanDuration {
+                    minutes,
+                    workdays: 0.0,
+                })
+            } else {
+                Err(invalid())
+            };
+        }
+        if let Some(iso) = input.strip_prefix('p') {
+            return parse_iso(iso).ok_or_else(invalid);
+        }
+
+        let mut duration = HumanDuration::from_minutes(0);
+        let mut rest = input.as_str();
+        while !rest.is_empty() {
+            let number_end = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .ok_or_else(invalid)?;
+            let unit_end = rest[number_end..]
+                .find(|c: char| c.is_ascii_digit() || c == '.')
+                .map_or(rest.len(), |i| number_end + i);
+
+            let amount: f64 = rest[..number_end].parse().map_err(|_| invalid())?;
+            duration.add(amount, &rest[number_end..unit_end])?;
+            rest = &rest[unit_end..];
+        }
+
+        Ok(duration)
+    }
+}
+
+/// The part after `P` in `PnDTnHnMnS`. Calendar units (years, months, weeks)
+/// have no fixed length in working time, so they aren't accepted.
+fn parse_iso(input: &str) -> Option<HumanDuration> {
+    let (date, time) = match input.split_once('t') {
+        Some((date, time)) => (date, Some(time)),
+        None => (input, None),
+    };
+    if date.is_empty() && time.is_none_or(str::is_empty) {
+        return None;
+    }
+
+    let mut duration = HumanDuration::from_minutes(0);
+    let mut read = |part: &str, units: &[char]| -> Option<()> {
+        let mut rest = part;
+        while !rest.is_empty() {
+            let end = rest.find(|c: char| c.is_ascii_alphabetic())?;
+            let unit = rest[end..].chars().next()?;
+            if !units.contains(&unit) {
+                return None;
+            }
+            let amount: f64 = rest[..end].parse().ok()?;
+            duration.add(amount, &unit.to_string()).ok()?;
+            rest = &rest[end + 1..];
+        }
+        Some(())
+    };
+
+    read(date, &['d'])?;
+    read(time.unwrap_or_default(), &['h', 'm', 's'])?;
+    Some(duration)
+}
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.workdays > 0.0 {
+            write!(f, "{}d", self.workdays)?;
+            if self.minutes == 0.0 {
+                return Ok(());
+            }
+        }
+        write!(f, "{}", format_duration(self.minutes.round() as u32))
+    }
+}
+
+/// `90` minutes is shown as `1h30m`. Days aren't used, since how long a day
+/// is depends on the config.
+pub fn format_duration(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h{}m", hours, minutes),
+    }
+}
+
+/// Serde support for minutes written as durations, e.g. `workday_length: 7h30m`.
+/// Plain numbers are still read as minutes.
+pub mod duration_minutes {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Minutes(u32),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(
+        minutes: &u32,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_duration(*minutes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> core::result::Result<u32, D::Error> {
+        match Raw::deserialize(deserializer)? {
+            Raw::Minutes(minutes) => Ok(minutes),
+            Raw::Text(text) => text
+                .parse::<HumanDuration>()
+                .and_then(|duration| duration.to_minutes(DEFAULT_WORKDAY_MINUTES))
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(input: &str) -> u32 {
+        input
+            .parse::<HumanDuration>()
+            .unwrap()
+            .to_minutes(DEFAULT_WORKDAY_MINUTES)
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_human_durations() {
+        assert_eq!(minutes("90"), 90);
+        assert_eq!(minutes("90m"), 90);
+        assert_eq!(minutes("1h30m"), 90);
+        assert_eq!(minutes("1h 30min"), 90);
+        assert_eq!(minutes("2.5h"), 150);
+        assert_eq!(minutes("1d"), DEFAULT_WORKDAY_MINUTES);
+        assert_eq!(minutes("1d2h"), DEFAULT_WORKDAY_MINUTES + 120);
+    }
+
+    #[test]
+    fn parses_iso_8601_durations() {
+        assert_eq!(minutes("PT1H30M"), 90);
+        assert_eq!(minutes("pt45m"), 45);
+        assert_eq!(minutes("PT90S"), 2);
+        assert_eq!(minutes("P1DT1H"), DEFAULT_WORKDAY_MINUTES + 60);
+    }
+
+    #[test]
+    fn days_use_the_workday_length() {
+        let duration: HumanDuration = "1.5d".parse().unwrap();
+
+        assert_eq!(duration.to_minutes(450).unwrap(), 675);
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for input in ["", "h", "1x", "1.2.3h", "-5", "P", "PT", "P1W", "PT1D"] {
+            assert!(input.parse::<HumanDuration>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn formats_minutes() {
+        assert_eq!(format_duration(45), "45m");
+        assert_eq!(format_duration(120), "2h");
+        assert_eq!(format_duration(90), "1h30m");
+    }
+}
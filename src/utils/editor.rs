@@ -0,0 +1,29 @@
+use crate::prelude::*;
+use std::process::Command;
+
+/// Opens `text` in the user's editor (`$VISUAL`, then `$EDITOR`, then `vi`)
+/// and returns what was saved.
+pub fn edit_text(text: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Editors are often configured with arguments, like `code --wait`
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| Error::Generic("$EDITOR is empty".to_string()))?;
+
+    let path = std::env::temp_dir().join(format!("auto-cal-{}.md", Uuid::new_v4()));
+    std::fs::write(&path, text)?;
+    let status = Command::new(program).args(words).arg(&path).status();
+    let edited = std::fs::read_to_string(&path);
+    std::fs::remove_file(&path)?;
+
+    if !status?.success() {
+        return Err(Error::Generic(format!(
+            "{} exited with an error; nothing was changed",
+            editor
+        )));
+    }
+    Ok(edited?)
+}
@@ -1 +1,4 @@
-use crate::prelude::*;
+pub mod date;
+pub mod duration;
+pub mod editor;
+pub mod timezone;
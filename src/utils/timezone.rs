@@ -0,0 +1,131 @@
+use crate::prelude::*;
+use chrono::{FixedOffset, Local, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+/// Follow the system time zone.
+pub const LOCAL_TIME_ZONE: &str = "local";
+
+/// The zone due times, the schedule and displayed timestamps are in. Named
+/// zones come from the tz database built into auto-cal, so they work the same
+/// on every platform.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Zone {
+    Local,
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+impl Zone {
+    /// Wall-clock time in this zone at the instant `at`.
+    pub fn local(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::Local => at.with_timezone(&Local).naive_local(),
+            Zone::Fixed(offset) => at.with_timezone(offset).naive_local(),
+            Zone::Named(zone) => at.with_timezone(zone).naive_local(),
+        }
+    }
+
+    /// The instant a wall-clock time in this zone refers to. Times skipped by
+    /// a daylight saving change don't exist; repeated ones resolve to the
+    /// earlier of the two.
+    pub fn utc(&self, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Zone::Local => Local
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|at| at.to_utc()),
+            Zone::Fixed(offset) => offset
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|at| at.to_utc()),
+            Zone::Named(zone) => zone
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|at| at.to_utc()),
+        }
+    }
+
+    pub fn now(&self) -> NaiveDateTime {
+        self.local(Utc::now())
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.now().date()
+    }
+}
+
+/// Reads the configured time zone: `local` follows the system zone, `UTC`
+/// and offsets like `+02:00` are fixed, and anything else must be an IANA
+/// name such as `Europe/Berlin`.
+impl FromStr for Zone {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let setting = s.trim();
+        if setting.is_empty() || setting.eq_ignore_ascii_case(LOCAL_TIME_ZONE) {
+            return Ok(Zone::Local);
+        }
+        if setting.eq_ignore_ascii_case("UTC") || setting.eq_ignore_ascii_case("Z") {
+            return Ok(Zone::Fixed(FixedOffset::east_opt(0).unwrap()));
+        }
+
+        if setting.starts_with(['+', '-']) {
+            return setting.parse().map(Zone::Fixed).map_err(|_| {
+                Error::Generic(format!(
+                    "Invalid time zone offset: {}. Expected something like +02:00",
+                    setting
+                ))
+            });
+        }
+
+        setting.parse().map(Zone::Named).map_err(|_| {
+            Error::Generic(format!(
+                "Unknown time zone: {}. Use local, UTC, an offset like +02:00 or a name like Europe/Berlin",
+                setting
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_zone_settings() {
+        let offset = |hours| Zone::Fixed(FixedOffset::east_opt(hours * 3600).unwrap());
+
+        assert_eq!("local".parse::<Zone>().unwrap(), Zone::Local);
+        assert_eq!("UTC".parse::<Zone>().unwrap(), offset(0));
+        assert_eq!("+02:00".parse::<Zone>().unwrap(), offset(2));
+        assert_eq!(
+            "Europe/Berlin".parse::<Zone>().unwrap(),
+            Zone::Named(chrono_tz::Europe::Berlin)
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_zones() {
+        assert!("Nowhere/Special".parse::<Zone>().is_err());
+        assert!("+25:00".parse::<Zone>().is_err());
+    }
+
+    #[test]
+    fn converts_between_wall_clock_time_and_instants() {
+        let zone: Zone = "America/New_York".parse().unwrap();
+        let at = NaiveDate::from_ymd_opt(2024, 7, 1)
+            .unwrap()
+            .and_hms_opt(16, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        let local = zone.local(at);
+
+        assert_eq!(
+            local.time(),
+            chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap()
+        );
+        assert_eq!(zone.utc(local), Some(at));
+    }
+}